@@ -0,0 +1,477 @@
+extern crate creusot_contracts;
+use creusot_contracts::{
+	ensures, invariant, predicate, proof_assert, requires,
+};
+
+#[cfg(feature = "contracts")]
+use creusot_contracts::{invariant::Invariant, pearlite, Int, Iterator, Seq};
+
+#[cfg(feature = "contracts")]
+use crate::{
+	check_sat::{
+		clauses_vars_in_range, complete_seq, consistent, is_ok, vars_in_range,
+		Assignment,
+	},
+	check_unsat::{lit_false, unassigned},
+};
+use crate::{
+	check_sat::{Clause, Lit, Var},
+	check_unsat::{clause_state, read_formula, ClauseState, UnsatError},
+};
+
+pub type ClauseId = u64;
+
+#[cfg_attr(not(feature = "contracts"), derive(Debug))]
+pub enum LratError {
+	ProofVarOutOfRange(ClauseId),
+	FormulaVarOutOfRange,
+	WrongNumberOfClauses,
+	// Lemma IDs have to be greater than every ID before them
+	IdNotIncreasing(ClauseId),
+	// The hints of the lemma with this ID dont lead to a conflict by unit
+	// propagation
+	StepFailed(ClauseId),
+	NoEmptyClause,
+}
+
+/// A step of an LRAT proof. Hints are the IDs of clauses that become unit in
+/// turn when the lemma is negated, the last of which conflicts. RAT hints are
+/// not supported
+pub enum LratStep {
+	Add {
+		id: ClauseId,
+		clause: Clause,
+		hints: Vec<ClauseId>,
+	},
+	Delete(Vec<ClauseId>),
+}
+
+#[predicate]
+fn lemmas_satisfied(
+	asn: Assignment,
+	lemmas: Seq<(ClauseId, Option<Clause>)>,
+) -> bool {
+	pearlite! {
+		forall<i: _, clause: Clause> 0 <= i && i < lemmas.len() ==>
+			lemmas[i].1 == Some(clause) ==> asn.l_satisfies_clause(@clause)
+	}
+}
+
+#[predicate]
+fn lemmas_implied(
+	formula: Seq<Clause>,
+	lemmas: Seq<(ClauseId, Option<Clause>)>,
+	max_var: Int,
+) -> bool {
+	pearlite! {
+		forall<asn: Assignment> (@asn.state).len() == max_var + 1 ==>
+			asn.satisfies(formula) ==> lemmas_satisfied(asn, lemmas)
+	}
+}
+
+#[predicate]
+fn lemmas_vars_in_range(
+	lemmas: Seq<(ClauseId, Option<Clause>)>,
+	max_var: Int,
+) -> bool {
+	pearlite! {
+		forall<i: _, clause: Clause> 0 <= i && i < lemmas.len() ==>
+			lemmas[i].1 == Some(clause) ==> vars_in_range(@clause, max_var)
+	}
+}
+
+#[predicate]
+fn refuted(formula: Seq<Clause>, max_var: Int) -> bool {
+	pearlite! {
+		forall<asn: Assignment> (@asn.state).len() == max_var + 1 ==>
+			!asn.satisfies(formula)
+	}
+}
+
+/// No literal made false by the hints so far is true in asn
+#[predicate]
+fn agrees_with(asn: Assignment, assignment: Seq<Option<bool>>) -> bool {
+	pearlite! {
+		forall<v: _, p: _> 0 <= v && v < assignment.len() ==>
+			assignment[v] == Some(p) ==> (@asn.state)[v] != Some(!p)
+	}
+}
+
+/// Index of the lemma with this ID in lemmas, found by bisection as the IDs
+/// are increasing
+#[ensures(forall<i: usize> result == Some(i) ==>
+	@i < (@lemmas).len() && (@lemmas)[@i].0 == id)]
+fn find_lemma(
+	lemmas: &[(ClauseId, Option<Clause>)],
+	id: ClauseId,
+) -> Option<usize> {
+	let mut low = 0;
+	let mut high = lemmas.len();
+	#[invariant(in_range, @low <= @high && @high <= (@lemmas).len())]
+	while low < high {
+		let mid = low + (high - low) / 2;
+		if lemmas[mid].0 < id {
+			low = mid + 1;
+		} else if lemmas[mid].0 > id {
+			high = mid;
+		} else {
+			return Some(mid);
+		}
+	}
+
+	None
+}
+
+#[requires((@formula_deleted).len() == (@formula).len())]
+#[ensures(forall<clause: &Clause> result == Some(clause) ==>
+	(exists<i: _> 0 <= i && i < (@formula).len() && (@formula)[i] == *clause) ||
+	(exists<i: _> 0 <= i && i < (@lemmas).len() &&
+		(@lemmas)[i].1 == Some(*clause)))]
+fn hint_clause<'a>(
+	formula: &'a [Clause],
+	formula_deleted: &[bool],
+	lemmas: &'a [(ClauseId, Option<Clause>)],
+	id: ClauseId,
+) -> Option<&'a Clause> {
+	let num_formula = formula.len() as ClauseId;
+
+	if id == 0 {
+		None
+	} else if id <= num_formula {
+		let i = (id - 1) as usize;
+		if formula_deleted[i] {
+			None
+		} else {
+			Some(&formula[i])
+		}
+	} else {
+		match find_lemma(lemmas, id) {
+			Some(i) => lemmas[i].1.as_ref(),
+			None => None,
+		}
+	}
+}
+
+/// Negates the lemma and then propagates each hint in turn, which has to be
+/// unit until one conflicts. Tautological lemmas fail. Variables assigned on
+/// the way are added to touched
+#[requires((@formula_deleted).len() == (@formula).len())]
+#[requires(clauses_vars_in_range(@formula, (@assignment).len() - 1))]
+#[requires(lemmas_vars_in_range(@lemmas, (@assignment).len() - 1))]
+#[requires(vars_in_range(@lemma, (@assignment).len() - 1))]
+#[requires((@assignment).len() > 0)]
+#[requires(unassigned(@assignment))]
+#[ensures((@^assignment).len() == (@assignment).len())]
+#[ensures(forall<v: _> 0 <= v && v < (@^assignment).len() ==>
+	(@^assignment)[v] != None ==>
+	exists<i: _> 0 <= i && i < (@^touched).len() && @(@^touched)[i] == v)]
+#[ensures(forall<i: _> 0 <= i && i < (@^touched).len() ==>
+	@(@^touched)[i] < (@assignment).len())]
+#[ensures(result ==> forall<asn: Assignment>
+	(@asn.state).len() == (@assignment).len() ==>
+	asn.satisfies(@formula) ==> lemmas_satisfied(asn, @lemmas) ==>
+	asn.l_satisfies_clause(@lemma))]
+fn propagate_hints(
+	formula: &[Clause],
+	formula_deleted: &[bool],
+	lemmas: &[(ClauseId, Option<Clause>)],
+	assignment: &mut [Option<bool>],
+	touched: &mut Vec<Var>,
+	lemma: &[Lit],
+	hints: &[ClauseId],
+) -> bool {
+	let mut i = 0;
+	#[invariant(i_in_range, 0 <= @i && @i <= (@lemma).len())]
+	#[invariant(consistent, consistent((@lemma).subsequence(0, @i)))]
+	#[invariant(negated, forall<v: _, p: _> 0 <= v && v < (@assignment).len() ==>
+		(@assignment)[v] == Some(p) ==> exists<j: _> 0 <= j && j < @i &&
+			(@lemma)[j].l_variable() == v && (@lemma)[j].l_polarity() != p)]
+	while i < lemma.len() {
+		let (variable, polarity) = lemma[i].var_pol();
+		match assignment[variable as usize] {
+			// The lemma contains x and -x. Tautologies are never needed in a
+			// refutation, so they are rejected rather than accepted unchecked
+			Some(assigned_pol) if assigned_pol == polarity => return false,
+			Some(_) => (),
+			None => touched.push(variable),
+		}
+		assignment[variable as usize] = Some(!polarity);
+		i += 1;
+	}
+
+	let mut h = 0;
+	#[invariant(h_in_range, 0 <= @h && @h <= (@hints).len())]
+	#[invariant(implied, forall<asn: Assignment>
+		(@asn.state).len() == (@assignment).len() ==>
+		asn.satisfies(@formula) ==> lemmas_satisfied(asn, @lemmas) ==>
+		!asn.l_satisfies_clause(@lemma) ==> agrees_with(asn, @assignment))]
+	while h < hints.len() {
+		let clause =
+			match hint_clause(formula, formula_deleted, lemmas, hints[h]) {
+				Some(clause) => clause,
+				None => return false,
+			};
+
+		match clause_state(assignment, clause) {
+			ClauseState::Open => return false,
+			ClauseState::Unit(lit) => {
+				assignment[lit.variable() as usize] = Some(lit.polarity());
+				touched.push(lit.variable());
+			}
+			ClauseState::Conflict => {
+				proof_assert!(forall<i: _> 0 <= i && i < (@clause).len() ==>
+					lit_false(@assignment, (@clause)[i]));
+				return true;
+			}
+		}
+		h += 1;
+	}
+
+	false
+}
+
+/// Checks the lemma using its hints, leaving the assignment unassigned again
+#[requires((@formula_deleted).len() == (@formula).len())]
+#[requires(clauses_vars_in_range(@formula, (@assignment).len() - 1))]
+#[requires(lemmas_vars_in_range(@lemmas, (@assignment).len() - 1))]
+#[requires(vars_in_range(@lemma, (@assignment).len() - 1))]
+#[requires((@assignment).len() > 0)]
+#[requires(unassigned(@assignment))]
+#[requires((@touched).len() == 0)]
+#[ensures((@^assignment).len() == (@assignment).len())]
+#[ensures(unassigned(@^assignment))]
+#[ensures((@^touched).len() == 0)]
+#[ensures(result ==> forall<asn: Assignment>
+	(@asn.state).len() == (@assignment).len() ==>
+	asn.satisfies(@formula) ==> lemmas_satisfied(asn, @lemmas) ==>
+	asn.l_satisfies_clause(@lemma))]
+fn hinted_rup(
+	formula: &[Clause],
+	formula_deleted: &[bool],
+	lemmas: &[(ClauseId, Option<Clause>)],
+	assignment: &mut [Option<bool>],
+	touched: &mut Vec<Var>,
+	lemma: &[Lit],
+	hints: &[ClauseId],
+) -> bool {
+	let implied = propagate_hints(
+		formula,
+		formula_deleted,
+		lemmas,
+		assignment,
+		touched,
+		lemma,
+		hints,
+	);
+
+	// Only the touched variables need resetting, so checking a step takes
+	// time in the size of its hints rather than the number of variables
+	let mut i = 0;
+	#[invariant(i_in_range, 0 <= @i && @i <= (@touched).len())]
+	#[invariant(reset, forall<v: _> 0 <= v && v < (@assignment).len() ==>
+		(@assignment)[v] != None ==>
+		exists<j: _> @i <= j && j < (@touched).len() && @(@touched)[j] == v)]
+	while i < touched.len() {
+		assignment[touched[i] as usize] = None;
+		i += 1;
+	}
+	touched.clear();
+
+	implied
+}
+
+/// Checks an LRAT proof that the formula is unsatisfiable. Lemmas are checked
+/// by unit propagation over just their hints, and the proof must reach the
+/// empty clause. Deleting an ID that doesnt refer to a clause is ignored
+#[requires(clauses.invariant())]
+#[requires(proof.invariant())]
+#[ensures(is_ok(result) ==> exists<seq: _> complete_seq(clauses, seq) && seq.len() == @num_clauses)]
+#[ensures(is_ok(result) ==> exists<seq: _> complete_seq(clauses, seq) && clauses_vars_in_range(seq, @max_var))]
+#[ensures(is_ok(result) ==> exists<seq: _> complete_seq(clauses, seq) && refuted(seq, @max_var))]
+pub fn check_lrat<ClauseIt, ProofIt>(
+	clauses: ClauseIt,
+	proof: ProofIt,
+	max_var: Var,
+	num_clauses: usize,
+) -> Result<(), LratError>
+where
+	ClauseIt: Iterator<Item = Clause>,
+	ProofIt: Iterator<Item = LratStep>,
+{
+	let formula = match read_formula(clauses, max_var, num_clauses) {
+		Err(UnsatError::FormulaVarOutOfRange) => {
+			return Err(LratError::FormulaVarOutOfRange)
+		}
+		Err(_) => return Err(LratError::WrongNumberOfClauses),
+		Ok(formula) => formula,
+	};
+	let num_formula = formula.len() as ClauseId;
+	let mut formula_deleted = vec![false; formula.len()];
+
+	// Lemma IDs follow the formula and can skip any number of IDs, so lemmas
+	// are kept with their IDs in increasing order and looked up by ID.
+	// Deleted lemmas are None
+	let mut lemmas: Vec<(ClauseId, Option<Clause>)> = Vec::new();
+	let mut last_id = num_formula;
+
+	let mut assignment = vec![None; max_var as usize + 1];
+	let mut touched = Vec::new();
+
+	#[invariant(iter_invar, iter.invariant())]
+	#[invariant(formula_deleted_len, (@formula_deleted).len() == (@formula).len())]
+	#[invariant(assignment_len, (@assignment).len() == @max_var + 1)]
+	#[invariant(unassigned, unassigned(@assignment))]
+	#[invariant(touched_empty, (@touched).len() == 0)]
+	#[invariant(lemmas_in_range, lemmas_vars_in_range(@lemmas, @max_var))]
+	#[invariant(lemmas_implied, lemmas_implied(@formula, @lemmas, @max_var))]
+	for step in proof {
+		match step {
+			LratStep::Add { id, clause, hints } => {
+				if id <= last_id {
+					return Err(LratError::IdNotIncreasing(id));
+				}
+
+				let mut i = 0;
+				#[invariant(i_in_range, 0 <= @i && @i <= (@clause).len())]
+				#[invariant(vars_in_range, vars_in_range((@clause).subsequence(0, @i), @max_var))]
+				while i < clause.len() {
+					if !clause[i].in_range(max_var) {
+						return Err(LratError::ProofVarOutOfRange(id));
+					}
+					i += 1;
+				}
+
+				if !hinted_rup(
+					&formula,
+					&formula_deleted,
+					&lemmas,
+					&mut assignment,
+					&mut touched,
+					&clause,
+					&hints,
+				) {
+					return Err(LratError::StepFailed(id));
+				}
+
+				if clause.is_empty() {
+					proof_assert!(refuted(@formula, @max_var));
+					return Ok(());
+				}
+
+				lemmas.push((id, Some(clause)));
+				last_id = id;
+			}
+			LratStep::Delete(ids) => {
+				let mut i = 0;
+				#[invariant(formula_deleted_len, (@formula_deleted).len() == (@formula).len())]
+				#[invariant(lemmas_in_range, lemmas_vars_in_range(@lemmas, @max_var))]
+				#[invariant(lemmas_implied, lemmas_implied(@formula, @lemmas, @max_var))]
+				while i < ids.len() {
+					let id = ids[i];
+					if id > num_formula {
+						if let Some(j) = find_lemma(&lemmas, id) {
+							lemmas[j].1 = None;
+						}
+					} else if id > 0 {
+						formula_deleted[(id - 1) as usize] = true;
+					}
+					i += 1;
+				}
+			}
+		}
+	}
+
+	Err(LratError::NoEmptyClause)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{check_lrat, ClauseId, LratError, LratStep};
+	use crate::check_sat::{Clause, Lit};
+
+	fn clause(lits: &[i32]) -> Clause {
+		lits.iter()
+			.map(|&lit| Lit::from_dimacs_unchecked(lit))
+			.collect()
+	}
+
+	fn add(id: ClauseId, lits: &[i32], hints: &[ClauseId]) -> LratStep {
+		LratStep::Add {
+			id,
+			clause: clause(lits),
+			hints: hints.to_vec(),
+		}
+	}
+
+	/// Checks a proof for the formula of all four clauses over 1 and 2, which
+	/// have IDs 1 to 4
+	fn check(proof: Vec<LratStep>) -> Result<(), LratError> {
+		let formula = [[1, 2], [-1, 2], [1, -2], [-1, -2]];
+		let formula = formula.iter().map(|lits| clause(lits));
+		check_lrat(formula, proof.into_iter(), 2, 4)
+	}
+
+	#[test]
+	fn verified() {
+		let proof = vec![add(5, &[2], &[1, 2]), add(6, &[], &[5, 3, 4])];
+		assert!(check(proof).is_ok());
+	}
+
+	#[test]
+	fn large_ids() {
+		let formula = [clause(&[1]), clause(&[-1])];
+		let proof = vec![
+			add(999_999_999_999, &[1], &[1]),
+			add(1_000_000_000_000, &[], &[999_999_999_999, 2]),
+		];
+		assert!(
+			check_lrat(formula.into_iter(), proof.into_iter(), 1, 2).is_ok()
+		);
+	}
+
+	#[test]
+	fn step_failed() {
+		let result = check(vec![add(5, &[2], &[1])]);
+		assert!(matches!(result, Err(LratError::StepFailed(5))));
+
+		let result = check(vec![add(5, &[2], &[1, 7])]);
+		assert!(matches!(result, Err(LratError::StepFailed(5))));
+	}
+
+	#[test]
+	fn id_not_increasing() {
+		let result = check(vec![add(4, &[2], &[1, 2])]);
+		assert!(matches!(result, Err(LratError::IdNotIncreasing(4))));
+
+		let proof = vec![add(10, &[2], &[1, 2]), add(7, &[2], &[1, 2])];
+		let result = check(proof);
+		assert!(matches!(result, Err(LratError::IdNotIncreasing(7))));
+	}
+
+	#[test]
+	fn tautology() {
+		let result = check(vec![add(5, &[1, -1], &[])]);
+		assert!(matches!(result, Err(LratError::StepFailed(5))));
+	}
+
+	#[test]
+	fn deletion() {
+		let proof = vec![LratStep::Delete(vec![2]), add(5, &[2], &[1, 2])];
+		let result = check(proof);
+		assert!(matches!(result, Err(LratError::StepFailed(5))));
+
+		let proof = vec![
+			add(5, &[2], &[1, 2]),
+			LratStep::Delete(vec![5, 99]),
+			add(6, &[], &[5, 3, 4]),
+		];
+		let result = check(proof);
+		assert!(matches!(result, Err(LratError::StepFailed(6))));
+	}
+
+	#[test]
+	fn no_empty_clause() {
+		let result = check(vec![add(5, &[2], &[1, 2])]);
+		assert!(matches!(result, Err(LratError::NoEmptyClause)));
+	}
+}
@@ -20,7 +20,7 @@ where
 }
 
 #[predicate]
-fn complete_seq<I, T>(iter: I, seq: Seq<T>) -> bool
+pub(crate) fn complete_seq<I, T>(iter: I, seq: Seq<T>) -> bool
 where
 	I: Iterator<Item = T>,
 {
@@ -30,14 +30,14 @@ where
 }
 
 #[predicate]
-fn iter_consumed<I: Iterator>(iter: I) -> bool {
+pub(crate) fn iter_consumed<I: Iterator>(iter: I) -> bool {
 	pearlite! {
 		exists<seq: _> complete_seq(iter, seq)
 	}
 }
 
 #[predicate]
-fn is_ok<T, E>(value: Result<T, E>) -> bool {
+pub(crate) fn is_ok<T, E>(value: Result<T, E>) -> bool {
 	pearlite! {
 		exists<t: _> value == Ok(t)
 	}
@@ -62,17 +62,17 @@ pub struct Lit {
 
 impl Lit {
 	#[logic]
-	fn l_variable(self) -> Int {
+	pub(crate) fn l_variable(self) -> Int {
 		pearlite! { (@self.lit).abs_diff(0) }
 	}
 
 	#[logic]
-	fn l_polarity(self) -> bool {
+	pub(crate) fn l_polarity(self) -> bool {
 		pearlite! { (@self.lit) >= 0 }
 	}
 
 	#[predicate]
-	fn l_in_range(self, max_var: Int) -> bool {
+	pub(crate) fn l_in_range(self, max_var: Int) -> bool {
 		pearlite! { self.l_variable() <= max_var }
 	}
 
@@ -96,13 +96,13 @@ impl Lit {
 	}
 
 	#[ensures(result == self.l_polarity())]
-	fn polarity(&self) -> bool {
+	pub(crate) fn polarity(&self) -> bool {
 		self.lit >= 0
 	}
 
 	#[ensures(@result.0 == self.l_variable())]
 	#[ensures(result.1 == self.l_polarity())]
-	fn var_pol(&self) -> (Var, bool) {
+	pub(crate) fn var_pol(&self) -> (Var, bool) {
 		(self.variable(), self.polarity())
 	}
 
@@ -112,7 +112,7 @@ impl Lit {
 	}
 
 	#[ensures(result == self.l_in_range(@max_var))]
-	fn in_range(&self, max_var: Var) -> bool {
+	pub(crate) fn in_range(&self, max_var: Var) -> bool {
 		self.variable() <= max_var
 	}
 }
@@ -135,26 +135,29 @@ impl DeepModel for Lit {
 	}
 }
 
-struct Assignment {
-	state: Vec<Option<bool>>,
+pub(crate) struct Assignment {
+	pub(crate) state: Vec<Option<bool>>,
 }
 
 #[predicate]
-fn vars_in_range(lits: Seq<Lit>, max_var: Int) -> bool {
+pub(crate) fn vars_in_range(lits: Seq<Lit>, max_var: Int) -> bool {
 	pearlite! {
 		forall<i: _> 0 <= i && i < lits.len() ==> lits[i].l_in_range(max_var)
 	}
 }
 
 #[predicate]
-fn clauses_vars_in_range(clauses: Seq<Clause>, max_var: Int) -> bool {
+pub(crate) fn clauses_vars_in_range(
+	clauses: Seq<Clause>,
+	max_var: Int,
+) -> bool {
 	pearlite! {
 		forall<i: _> 0 <= i && i < clauses.len() ==> vars_in_range(@clauses[i], max_var)
 	}
 }
 
 #[predicate]
-fn consistent(lits: Seq<Lit>) -> bool {
+pub(crate) fn consistent(lits: Seq<Lit>) -> bool {
 	pearlite! {
 		forall<i: _, j: _> 0 <= i && i < lits.len() && 0 <= j && j < lits.len() ==>
 			!lits[i].conflicts_with(lits[j])
@@ -274,14 +277,14 @@ impl Assignment {
 	}
 
 	#[predicate]
-	fn l_satisfies_lit(self, lit: Lit) -> bool {
+	pub(crate) fn l_satisfies_lit(self, lit: Lit) -> bool {
 		pearlite! {
 			(@self.state)[lit.l_variable()] == Some(lit.l_polarity())
 		}
 	}
 
 	#[predicate]
-	fn l_satisfies_clause(self, clause: Seq<Lit>) -> bool {
+	pub(crate) fn l_satisfies_clause(self, clause: Seq<Lit>) -> bool {
 		pearlite! {
 			exists<i: _> 0 <= i && i < clause.len() &&
 				self.l_satisfies_lit(clause[i])
@@ -289,7 +292,7 @@ impl Assignment {
 	}
 
 	#[predicate]
-	fn satisfies(self, clauses: Seq<Clause>) -> bool {
+	pub(crate) fn satisfies(self, clauses: Seq<Clause>) -> bool {
 		pearlite! {
 			forall<i: _> 0 <= i && i < clauses.len() ==>
 				self.l_satisfies_clause(@clauses[i])
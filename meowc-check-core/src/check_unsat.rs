@@ -0,0 +1,839 @@
+extern crate creusot_contracts;
+use creusot_contracts::{
+	ensures, invariant, predicate, proof_assert, requires,
+};
+
+#[cfg(feature = "contracts")]
+use creusot_contracts::{invariant::Invariant, pearlite, Int, Iterator, Seq};
+
+#[cfg(feature = "contracts")]
+use crate::check_sat::{
+	clauses_vars_in_range, complete_seq, is_ok, iter_consumed, vars_in_range,
+};
+use crate::check_sat::{Clause, Lit, Var};
+
+#[cfg_attr(not(feature = "contracts"), derive(Debug))]
+pub enum UnsatError {
+	ProofVarOutOfRange,
+	FormulaVarOutOfRange,
+	WrongNumberOfClauses,
+	LemmaNotImplied,
+	NoEmptyClause,
+}
+
+/// A step of a DRAT proof. Only lemmas implied by reverse unit propagation
+/// are supported, so a RUP proof is the same with no deletions. meowc-sat
+/// only writes RAT lemmas when restoring removed clauses while solving
+/// incrementally
+pub enum ProofStep {
+	Add(Clause),
+	Delete(Clause),
+}
+
+// A model is a total assignment, indexed by variable
+#[predicate]
+fn model_satisfies_lit(model: Seq<bool>, lit: Lit) -> bool {
+	pearlite! { model[lit.l_variable()] == lit.l_polarity() }
+}
+
+#[predicate]
+fn model_satisfies_clause(model: Seq<bool>, clause: Seq<Lit>) -> bool {
+	pearlite! {
+		exists<i: _> 0 <= i && i < clause.len() &&
+			model_satisfies_lit(model, clause[i])
+	}
+}
+
+#[predicate]
+fn model_satisfies(model: Seq<bool>, clauses: Seq<Clause>) -> bool {
+	pearlite! {
+		forall<i: _> 0 <= i && i < clauses.len() ==>
+			model_satisfies_clause(model, @clauses[i])
+	}
+}
+
+#[predicate]
+fn implies(formula: Seq<Clause>, clauses: Seq<Clause>, max_var: Int) -> bool {
+	pearlite! {
+		forall<model: Seq<bool>> model.len() == max_var + 1 ==>
+			model_satisfies(model, formula) ==> model_satisfies(model, clauses)
+	}
+}
+
+#[predicate]
+fn unsatisfiable(formula: Seq<Clause>, max_var: Int) -> bool {
+	pearlite! {
+		forall<model: Seq<bool>> model.len() == max_var + 1 ==>
+			!model_satisfies(model, formula)
+	}
+}
+
+// Assignments built up by unit propagation are partial
+#[predicate]
+pub(crate) fn lit_false(assignment: Seq<Option<bool>>, lit: Lit) -> bool {
+	pearlite! { assignment[lit.l_variable()] == Some(!lit.l_polarity()) }
+}
+
+#[predicate]
+fn compatible(model: Seq<bool>, assignment: Seq<Option<bool>>) -> bool {
+	pearlite! {
+		forall<v: _, p: _> 0 <= v && v < assignment.len() ==>
+			assignment[v] == Some(p) ==> model[v] == p
+	}
+}
+
+/// Every model of the formula and lemmas that falsifies the lemma being
+/// checked agrees with the assignment
+#[predicate]
+fn rup_invariant(
+	formula: Seq<Clause>,
+	lemmas: Seq<Clause>,
+	lemma: Seq<Lit>,
+	assignment: Seq<Option<bool>>,
+) -> bool {
+	pearlite! {
+		forall<model: Seq<bool>> model.len() == assignment.len() ==>
+			model_satisfies(model, formula) ==>
+			model_satisfies(model, lemmas) ==>
+			!model_satisfies_clause(model, lemma) ==>
+			compatible(model, assignment)
+	}
+}
+
+#[predicate]
+fn same_lits(a: Seq<Lit>, b: Seq<Lit>) -> bool {
+	pearlite! {
+		forall<i: _> 0 <= i && i < a.len() ==>
+			exists<j: _> 0 <= j && j < b.len() && a[i] == b[j]
+	}
+}
+
+#[requires(lit.l_variable() < (@assignment).len())]
+#[ensures(result == Some(true) ==>
+	(@assignment)[lit.l_variable()] == Some(lit.l_polarity()))]
+#[ensures(result == Some(false) ==> lit_false(@assignment, lit))]
+#[ensures(result == None ==> (@assignment)[lit.l_variable()] == None)]
+fn lit_value(assignment: &[Option<bool>], lit: Lit) -> Option<bool> {
+	match assignment[lit.variable() as usize] {
+		Some(pol) if pol == lit.polarity() => Some(true),
+		Some(_) => Some(false),
+		None => None,
+	}
+}
+
+pub(crate) enum ClauseState {
+	Open,
+	Unit(Lit),
+	Conflict,
+}
+
+#[requires((@assignment).len() > 0)]
+#[requires(vars_in_range(@clause, (@assignment).len() - 1))]
+#[ensures(result == ClauseState::Conflict ==>
+	forall<i: _> 0 <= i && i < (@clause).len() ==>
+		lit_false(@assignment, (@clause)[i]))]
+#[ensures(forall<lit: Lit> result == ClauseState::Unit(lit) ==>
+	(@assignment)[lit.l_variable()] == None &&
+	forall<i: _> 0 <= i && i < (@clause).len() ==>
+		(@clause)[i] == lit || lit_false(@assignment, (@clause)[i]))]
+pub(crate) fn clause_state(
+	assignment: &[Option<bool>],
+	clause: &[Lit],
+) -> ClauseState {
+	let mut unit: Option<Lit> = None;
+	let mut i = 0;
+
+	#[invariant(i_in_range, 0 <= @i && @i <= (@clause).len())]
+	#[invariant(unit_unassigned, forall<lit: Lit> unit == Some(lit) ==>
+		(@assignment)[lit.l_variable()] == None)]
+	#[invariant(rest_false, forall<j: _> 0 <= j && j < @i ==>
+		unit == Some((@clause)[j]) || lit_false(@assignment, (@clause)[j]))]
+	while i < clause.len() {
+		let lit = clause[i];
+		match lit_value(assignment, lit) {
+			Some(true) => return ClauseState::Open,
+			Some(false) => (),
+			None => match unit {
+				None => unit = Some(lit),
+				// Two unassigned literals, or x and -x which is always
+				// satisfied
+				Some(other) => {
+					if other != lit {
+						return ClauseState::Open;
+					}
+				}
+			},
+		}
+		i += 1;
+	}
+
+	match unit {
+		None => ClauseState::Conflict,
+		Some(lit) => ClauseState::Unit(lit),
+	}
+}
+
+/// No variable is assigned
+#[predicate]
+pub(crate) fn unassigned(assignment: Seq<Option<bool>>) -> bool {
+	pearlite! {
+		forall<v: _> 0 <= v && v < assignment.len() ==> assignment[v] == None
+	}
+}
+
+/// Every assigned variable is on the trail
+#[predicate]
+fn on_trail(assignment: Seq<Option<bool>>, trail: Seq<Var>) -> bool {
+	pearlite! {
+		forall<v: _> 0 <= v && v < assignment.len() ==> assignment[v] != None ==>
+			exists<i: _> 0 <= i && i < trail.len() && @trail[i] == v
+	}
+}
+
+#[predicate]
+fn trail_in_range(trail: Seq<Var>, max_var: Int) -> bool {
+	pearlite! {
+		forall<i: _> 0 <= i && i < trail.len() ==> @trail[i] <= max_var
+	}
+}
+
+/// Index of lit in the occurrence lists, which have a list for each polarity
+/// of each variable
+#[ensures(@result == 2 * lit.l_variable() + if lit.l_polarity() { 1 } else { 0 })]
+fn lit_index(lit: Lit) -> usize {
+	let (variable, polarity) = lit.var_pol();
+	2 * variable as usize + polarity as usize
+}
+
+/// Clauses are referred to by index, the formula first and then the lemmas.
+/// lists holds the clauses containing each literal, indexed by lit_index.
+/// units holds the clauses with no more than one distinct literal, which
+/// propagate without any of their literals becoming false
+struct Occurrences {
+	lists: Vec<Vec<usize>>,
+	units: Vec<usize>,
+}
+
+impl Occurrences {
+	#[ensures((@result.lists).len() == 2 * @max_var + 2)]
+	fn new(max_var: Var) -> Self {
+		Self {
+			lists: vec![Vec::new(); 2 * max_var as usize + 2],
+			units: Vec::new(),
+		}
+	}
+
+	#[requires((@self.lists).len() > 0)]
+	#[requires(vars_in_range(@clause, (@self.lists).len() / 2 - 1))]
+	#[ensures((@(^self).lists).len() == (@self.lists).len())]
+	fn add(&mut self, index: usize, clause: &[Lit]) {
+		let mut unit = true;
+		let mut i = 0;
+		#[invariant(i_in_range, 0 <= @i && @i <= (@clause).len())]
+		#[invariant(lists_len, (@self.lists).len() == (@(*old_self).lists).len())]
+		while i < clause.len() {
+			if clause[i] != clause[0] {
+				unit = false;
+			}
+			self.lists[lit_index(clause[i])].push(index);
+			i += 1;
+		}
+
+		if unit {
+			self.units.push(index);
+		}
+	}
+}
+
+/// The clause with this index, unless it is deleted
+#[requires((@formula_deleted).len() == (@formula).len())]
+#[requires((@lemmas_deleted).len() == (@lemmas).len())]
+#[ensures(forall<clause: &Clause> result == Some(clause) ==>
+	(exists<i: _> 0 <= i && i < (@formula).len() && (@formula)[i] == *clause) ||
+	(exists<i: _> 0 <= i && i < (@lemmas).len() && (@lemmas)[i] == *clause))]
+fn indexed_clause<'a>(
+	formula: &'a [Clause],
+	formula_deleted: &[bool],
+	lemmas: &'a [Clause],
+	lemmas_deleted: &[bool],
+	index: usize,
+) -> Option<&'a Clause> {
+	if index < formula.len() {
+		if formula_deleted[index] {
+			None
+		} else {
+			Some(&formula[index])
+		}
+	} else if index - formula.len() < lemmas.len() {
+		let i = index - formula.len();
+		if lemmas_deleted[i] {
+			None
+		} else {
+			Some(&lemmas[i])
+		}
+	} else {
+		None
+	}
+}
+
+/// Assigns the literal the clause is unit on, if it is, adding its variable
+/// to the trail. Returns whether the clause conflicts
+#[requires((@assignment).len() > 0)]
+#[requires(vars_in_range(@clause, (@assignment).len() - 1))]
+#[ensures((@^assignment).len() == (@assignment).len())]
+#[ensures(result ==> forall<i: _> 0 <= i && i < (@clause).len() ==>
+	lit_false(@assignment, (@clause)[i]))]
+#[ensures(forall<v: _, p: _> 0 <= v && v < (@assignment).len() ==>
+	(@assignment)[v] == Some(p) ==> (@^assignment)[v] == Some(p))]
+#[ensures(forall<v: _> 0 <= v && v < (@assignment).len() ==>
+	(@^assignment)[v] != (@assignment)[v] ==>
+	exists<i: _> 0 <= i && i < (@clause).len() &&
+		(@clause)[i].l_variable() == v &&
+		(@^assignment)[v] == Some((@clause)[i].l_polarity()) &&
+		forall<j: _> 0 <= j && j < (@clause).len() ==>
+			(@clause)[j] == (@clause)[i] || lit_false(@assignment, (@clause)[j]))]
+#[ensures((@^trail).len() >= (@trail).len())]
+#[ensures(forall<i: _> 0 <= i && i < (@trail).len() ==> (@^trail)[i] == (@trail)[i])]
+#[ensures(on_trail(@assignment, @trail) ==> on_trail(@^assignment, @^trail))]
+#[ensures(trail_in_range(@trail, (@assignment).len() - 1) ==>
+	trail_in_range(@^trail, (@assignment).len() - 1))]
+fn propagate_clause(
+	assignment: &mut [Option<bool>],
+	trail: &mut Vec<Var>,
+	clause: &[Lit],
+) -> bool {
+	match clause_state(assignment, clause) {
+		ClauseState::Open => false,
+		ClauseState::Unit(lit) => {
+			assignment[lit.variable() as usize] = Some(lit.polarity());
+			trail.push(lit.variable());
+			false
+		}
+		ClauseState::Conflict => true,
+	}
+}
+
+/// Unit propagates from the units and then from each variable on the trail in
+/// turn, visiting only the clauses its value can make unit or conflicting.
+/// Variables that are assigned are added to the trail. Returns whether there
+/// is a conflict
+#[requires((@formula_deleted).len() == (@formula).len())]
+#[requires((@lemmas_deleted).len() == (@lemmas).len())]
+#[requires((@assignment).len() > 0)]
+#[requires((@occurrences.lists).len() == 2 * (@assignment).len())]
+#[requires(clauses_vars_in_range(@formula, (@assignment).len() - 1))]
+#[requires(clauses_vars_in_range(@lemmas, (@assignment).len() - 1))]
+#[requires(trail_in_range(@trail, (@assignment).len() - 1))]
+#[requires(on_trail(@assignment, @trail))]
+#[ensures((@^assignment).len() == (@assignment).len())]
+#[ensures(trail_in_range(@^trail, (@assignment).len() - 1))]
+#[ensures(on_trail(@^assignment, @^trail))]
+#[ensures(forall<lemma: Seq<Lit>>
+	rup_invariant(@formula, @lemmas, lemma, @assignment) ==>
+	rup_invariant(@formula, @lemmas, lemma, @^assignment))]
+#[ensures(result ==> forall<model: Seq<bool>> model.len() == (@assignment).len() ==>
+	model_satisfies(model, @formula) ==> model_satisfies(model, @lemmas) ==>
+	!compatible(model, @^assignment))]
+fn propagate(
+	formula: &[Clause],
+	formula_deleted: &[bool],
+	lemmas: &[Clause],
+	lemmas_deleted: &[bool],
+	occurrences: &Occurrences,
+	assignment: &mut [Option<bool>],
+	trail: &mut Vec<Var>,
+) -> bool {
+	let mut i = 0;
+	#[invariant(assignment_len, (@assignment).len() == (@*old_assignment).len())]
+	#[invariant(trail_in_range, trail_in_range(@trail, (@assignment).len() - 1))]
+	#[invariant(on_trail, on_trail(@assignment, @trail))]
+	while i < occurrences.units.len() {
+		let index = occurrences.units[i];
+		if let Some(clause) = indexed_clause(
+			formula,
+			formula_deleted,
+			lemmas,
+			lemmas_deleted,
+			index,
+		) {
+			if propagate_clause(assignment, trail, clause) {
+				return true;
+			}
+		}
+		i += 1;
+	}
+
+	let mut head = 0;
+	#[invariant(assignment_len, (@assignment).len() == (@*old_assignment).len())]
+	#[invariant(trail_in_range, trail_in_range(@trail, (@assignment).len() - 1))]
+	#[invariant(on_trail, on_trail(@assignment, @trail))]
+	while head < trail.len() {
+		// Only clauses with a literal the variable makes false are visited
+		let variable = trail[head] as usize;
+		let value = assignment[variable] == Some(true);
+		let list = &occurrences.lists[2 * variable + (!value) as usize];
+
+		let mut j = 0;
+		#[invariant(assignment_len, (@assignment).len() == (@*old_assignment).len())]
+		#[invariant(trail_in_range, trail_in_range(@trail, (@assignment).len() - 1))]
+		#[invariant(on_trail, on_trail(@assignment, @trail))]
+		while j < list.len() {
+			if let Some(clause) = indexed_clause(
+				formula,
+				formula_deleted,
+				lemmas,
+				lemmas_deleted,
+				list[j],
+			) {
+				if propagate_clause(assignment, trail, clause) {
+					return true;
+				}
+			}
+			j += 1;
+		}
+		head += 1;
+	}
+
+	false
+}
+
+/// Checks that lemma is implied by the clauses that arent deleted by reverse
+/// unit propagation, i.e. that unit propagation after assigning every
+/// literal of lemma false leads to a conflict. The assignment is left
+/// unassigned again
+#[requires((@formula_deleted).len() == (@formula).len())]
+#[requires((@lemmas_deleted).len() == (@lemmas).len())]
+#[requires((@assignment).len() > 0)]
+#[requires((@occurrences.lists).len() == 2 * (@assignment).len())]
+#[requires(clauses_vars_in_range(@formula, (@assignment).len() - 1))]
+#[requires(clauses_vars_in_range(@lemmas, (@assignment).len() - 1))]
+#[requires(vars_in_range(@lemma, (@assignment).len() - 1))]
+#[requires(unassigned(@assignment))]
+#[ensures((@^assignment).len() == (@assignment).len())]
+#[ensures(unassigned(@^assignment))]
+#[ensures(result ==> forall<model: Seq<bool>> model.len() == (@assignment).len() ==>
+	model_satisfies(model, @formula) ==>
+	model_satisfies(model, @lemmas) ==>
+	model_satisfies_clause(model, @lemma))]
+fn rup(
+	formula: &[Clause],
+	formula_deleted: &[bool],
+	lemmas: &[Clause],
+	lemmas_deleted: &[bool],
+	occurrences: &Occurrences,
+	assignment: &mut [Option<bool>],
+	lemma: &[Lit],
+) -> bool {
+	let mut trail = Vec::new();
+	let mut implied = false;
+
+	let mut i = 0;
+	#[invariant(i_in_range, 0 <= @i && @i <= (@lemma).len())]
+	#[invariant(assignment_len, (@assignment).len() == (@*old_assignment).len())]
+	#[invariant(negated, forall<v: _, p: _> 0 <= v && v < (@assignment).len() ==>
+		(@assignment)[v] == Some(p) ==> exists<j: _> 0 <= j && j < @i &&
+			(@lemma)[j].l_variable() == v && (@lemma)[j].l_polarity() != p)]
+	#[invariant(trail_in_range, trail_in_range(@trail, (@assignment).len() - 1))]
+	#[invariant(on_trail, on_trail(@assignment, @trail))]
+	while i < lemma.len() {
+		let (variable, polarity) = lemma[i].var_pol();
+		match assignment[variable as usize] {
+			// The lemma contains x and -x so is satisfied by every model
+			Some(assigned_pol) if assigned_pol == polarity => {
+				implied = true;
+				break;
+			}
+			Some(_) => (),
+			None => {
+				assignment[variable as usize] = Some(!polarity);
+				trail.push(variable);
+			}
+		}
+		i += 1;
+	}
+
+	if !implied {
+		implied = propagate(
+			formula,
+			formula_deleted,
+			lemmas,
+			lemmas_deleted,
+			occurrences,
+			assignment,
+			&mut trail,
+		);
+	}
+
+	// Only the variables on the trail need resetting, so checking a lemma
+	// takes time in the number of assignments rather than of variables
+	let mut i = 0;
+	#[invariant(i_in_range, 0 <= @i && @i <= (@trail).len())]
+	#[invariant(assignment_len, (@assignment).len() == (@*old_assignment).len())]
+	#[invariant(reset, forall<v: _> 0 <= v && v < (@assignment).len() ==>
+		(@assignment)[v] != None ==>
+		exists<j: _> @i <= j && j < (@trail).len() && @(@trail)[j] == v)]
+	while i < trail.len() {
+		assignment[trail[i] as usize] = None;
+		i += 1;
+	}
+
+	implied
+}
+
+#[ensures(result == exists<i: _> 0 <= i && i < (@clause).len() && (@clause)[i] == lit)]
+fn contains(clause: &[Lit], lit: Lit) -> bool {
+	let mut i = 0;
+
+	#[invariant(i_in_range, 0 <= @i && @i <= (@clause).len())]
+	#[invariant(not_found, forall<j: _> 0 <= j && j < @i ==> (@clause)[j] != lit)]
+	while i < clause.len() {
+		if clause[i] == lit {
+			return true;
+		}
+		i += 1;
+	}
+
+	false
+}
+
+/// Whether the clauses have the same literals, ignoring order and repetition
+#[ensures(result ==> same_lits(@a, @b) && same_lits(@b, @a))]
+fn same_clause(a: &[Lit], b: &[Lit]) -> bool {
+	let mut i = 0;
+	#[invariant(i_in_range, 0 <= @i && @i <= (@a).len())]
+	#[invariant(found, same_lits((@a).subsequence(0, @i), @b))]
+	while i < a.len() {
+		if !contains(b, a[i]) {
+			return false;
+		}
+		i += 1;
+	}
+
+	let mut i = 0;
+	#[invariant(i_in_range, 0 <= @i && @i <= (@b).len())]
+	#[invariant(found, same_lits((@b).subsequence(0, @i), @a))]
+	while i < b.len() {
+		if !contains(a, b[i]) {
+			return false;
+		}
+		i += 1;
+	}
+
+	true
+}
+
+/// Marks the most recently added clause matching clause as deleted, so
+/// lemmas are deleted before formula clauses. Only the clauses containing the
+/// first literal of clause are compared
+#[requires((@formula_deleted).len() == (@formula).len())]
+#[requires((@lemmas_deleted).len() == (@lemmas).len())]
+#[ensures((@^formula_deleted).len() == (@formula_deleted).len())]
+#[ensures((@^lemmas_deleted).len() == (@lemmas_deleted).len())]
+fn delete(
+	formula: &[Clause],
+	formula_deleted: &mut [bool],
+	lemmas: &[Clause],
+	lemmas_deleted: &mut [bool],
+	occurrences: &Occurrences,
+	clause: &[Lit],
+) {
+	let candidates = if clause.is_empty() {
+		&occurrences.units
+	} else {
+		let index = lit_index(clause[0]);
+		if index >= occurrences.lists.len() {
+			return;
+		}
+		&occurrences.lists[index]
+	};
+
+	let mut i = candidates.len();
+	#[invariant(i_in_range, @i <= (@candidates).len())]
+	#[invariant(formula_deleted_len, (@formula_deleted).len() == (@formula).len())]
+	#[invariant(lemmas_deleted_len, (@lemmas_deleted).len() == (@lemmas).len())]
+	while i > 0 {
+		i -= 1;
+		let index = candidates[i];
+		if index < formula.len() {
+			if !formula_deleted[index] && same_clause(&formula[index], clause) {
+				formula_deleted[index] = true;
+				return;
+			}
+		} else if index - formula.len() < lemmas.len() {
+			let j = index - formula.len();
+			if !lemmas_deleted[j] && same_clause(&lemmas[j], clause) {
+				lemmas_deleted[j] = true;
+				return;
+			}
+		}
+	}
+}
+
+#[requires(clauses.invariant())]
+#[ensures(forall<formula: _> result == Ok(formula) ==>
+	complete_seq(clauses, @formula))]
+#[ensures(forall<formula: _> result == Ok(formula) ==>
+	(@formula).len() == @num_clauses)]
+#[ensures(forall<formula: _> result == Ok(formula) ==>
+	clauses_vars_in_range(@formula, @max_var))]
+pub(crate) fn read_formula<ClauseIt>(
+	clauses: ClauseIt,
+	max_var: Var,
+	num_clauses: usize,
+) -> Result<Vec<Clause>, UnsatError>
+where
+	ClauseIt: Iterator<Item = Clause>,
+{
+	let mut formula = Vec::new();
+
+	#[invariant(iter_invar, iter.invariant())]
+	#[invariant(read, @formula == produced.inner())]
+	#[invariant(vars_in_range, clauses_vars_in_range(produced.inner(), @max_var))]
+	#[invariant(num_clauses_check, (@formula).len() <= @num_clauses)]
+	for clause in clauses {
+		proof_assert!(clause == produced[produced.len() - 1]);
+
+		let mut i = 0;
+		#[invariant(i_in_range, 0 <= @i && @i <= (@clause).len())]
+		#[invariant(vars_in_range, vars_in_range((@clause).subsequence(0, @i), @max_var))]
+		while i < clause.len() {
+			if !clause[i].in_range(max_var) {
+				return Err(UnsatError::FormulaVarOutOfRange);
+			}
+			i += 1;
+		}
+
+		if formula.len() >= num_clauses {
+			return Err(UnsatError::WrongNumberOfClauses);
+		}
+
+		formula.push(clause);
+	}
+
+	if formula.len() != num_clauses {
+		return Err(UnsatError::WrongNumberOfClauses);
+	}
+
+	Ok(formula)
+}
+
+/// Checks a DRAT or RUP proof that the formula is unsatisfiable. Each lemma
+/// must be implied by reverse unit propagation over the formula and the
+/// lemmas before it, less the clauses deleted so far, and the proof must
+/// reach the empty clause. Lemmas that are only RAT are rejected as
+/// LemmaNotImplied. Deletions of clauses that arent present are ignored, as
+/// other DRAT checkers do
+#[requires(clauses.invariant())]
+#[requires(proof.invariant())]
+#[ensures(is_ok(result) ==> iter_consumed(clauses))]
+#[ensures(is_ok(result) ==> exists<seq: _> complete_seq(clauses, seq) && seq.len() == @num_clauses)]
+#[ensures(is_ok(result) ==> exists<seq: _> complete_seq(clauses, seq) && clauses_vars_in_range(seq, @max_var))]
+#[ensures(is_ok(result) ==> exists<seq: _> complete_seq(clauses, seq) && unsatisfiable(seq, @max_var))]
+pub fn check_unsat<ClauseIt, ProofIt>(
+	clauses: ClauseIt,
+	proof: ProofIt,
+	max_var: Var,
+	num_clauses: usize,
+) -> Result<(), UnsatError>
+where
+	ClauseIt: Iterator<Item = Clause>,
+	ProofIt: Iterator<Item = ProofStep>,
+{
+	let formula = match read_formula(clauses, max_var, num_clauses) {
+		Err(e) => return Err(e),
+		Ok(formula) => formula,
+	};
+	let mut formula_deleted = vec![false; formula.len()];
+
+	let mut lemmas: Vec<Clause> = Vec::new();
+	let mut lemmas_deleted: Vec<bool> = Vec::new();
+
+	let mut occurrences = Occurrences::new(max_var);
+	let mut i = 0;
+	#[invariant(i_in_range, 0 <= @i && @i <= (@formula).len())]
+	#[invariant(lists_len, (@occurrences.lists).len() == 2 * @max_var + 2)]
+	while i < formula.len() {
+		occurrences.add(i, &formula[i]);
+		i += 1;
+	}
+
+	// Allocated once, as rup leaves it unassigned
+	let mut assignment = vec![None; max_var as usize + 1];
+
+	#[invariant(iter_invar, iter.invariant())]
+	#[invariant(assignment_len, (@assignment).len() == @max_var + 1)]
+	#[invariant(unassigned, unassigned(@assignment))]
+	#[invariant(lists_len, (@occurrences.lists).len() == 2 * @max_var + 2)]
+	#[invariant(formula_deleted_len, (@formula_deleted).len() == (@formula).len())]
+	#[invariant(lemmas_deleted_len, (@lemmas_deleted).len() == (@lemmas).len())]
+	#[invariant(lemmas_in_range, clauses_vars_in_range(@lemmas, @max_var))]
+	#[invariant(lemmas_implied, implies(@formula, @lemmas, @max_var))]
+	for step in proof {
+		match step {
+			ProofStep::Add(lemma) => {
+				let mut i = 0;
+				#[invariant(i_in_range, 0 <= @i && @i <= (@lemma).len())]
+				#[invariant(vars_in_range, vars_in_range((@lemma).subsequence(0, @i), @max_var))]
+				while i < lemma.len() {
+					if !lemma[i].in_range(max_var) {
+						return Err(UnsatError::ProofVarOutOfRange);
+					}
+					i += 1;
+				}
+
+				if !rup(
+					&formula,
+					&formula_deleted,
+					&lemmas,
+					&lemmas_deleted,
+					&occurrences,
+					&mut assignment,
+					&lemma,
+				) {
+					return Err(UnsatError::LemmaNotImplied);
+				}
+
+				if lemma.is_empty() {
+					proof_assert!(unsatisfiable(@formula, @max_var));
+					return Ok(());
+				}
+
+				occurrences.add(formula.len() + lemmas.len(), &lemma);
+				lemmas.push(lemma);
+				lemmas_deleted.push(false);
+			}
+			ProofStep::Delete(clause) => {
+				delete(
+					&formula,
+					&mut formula_deleted,
+					&lemmas,
+					&mut lemmas_deleted,
+					&occurrences,
+					&clause,
+				);
+			}
+		}
+	}
+
+	Err(UnsatError::NoEmptyClause)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{check_unsat, ProofStep, UnsatError};
+	use crate::check_sat::{Clause, Lit};
+
+	fn clause(lits: &[i32]) -> Clause {
+		lits.iter()
+			.map(|&lit| Lit::from_dimacs_unchecked(lit))
+			.collect()
+	}
+
+	fn add(lits: &[i32]) -> ProofStep {
+		ProofStep::Add(clause(lits))
+	}
+
+	fn delete(lits: &[i32]) -> ProofStep {
+		ProofStep::Delete(clause(lits))
+	}
+
+	/// Checks a proof for the formula of all four clauses over 1 and 2
+	fn check(proof: Vec<ProofStep>) -> Result<(), UnsatError> {
+		let formula = [[1, 2], [-1, 2], [1, -2], [-1, -2]];
+		let formula = formula.iter().map(|lits| clause(lits));
+		check_unsat(formula, proof.into_iter(), 2, 4)
+	}
+
+	#[test]
+	fn verified() {
+		assert!(check(vec![add(&[2]), add(&[])]).is_ok());
+		assert!(check(vec![add(&[2]), delete(&[1, 2]), add(&[])]).is_ok());
+	}
+
+	#[test]
+	fn propagation_chain() {
+		let formula = [
+			clause(&[1]),
+			clause(&[-1, 2]),
+			clause(&[-2, 3]),
+			clause(&[-3, 4]),
+			clause(&[-4, -1]),
+		];
+		let proof = vec![add(&[])];
+		assert!(
+			check_unsat(formula.into_iter(), proof.into_iter(), 4, 5).is_ok()
+		);
+	}
+
+	#[test]
+	fn empty_clause_in_formula() {
+		let formula = [clause(&[1, 2]), clause(&[])];
+		let proof = vec![add(&[])];
+		assert!(
+			check_unsat(formula.into_iter(), proof.into_iter(), 2, 2).is_ok()
+		);
+	}
+
+	#[test]
+	fn lemma_not_implied() {
+		// No clause of the formula is unit
+		let result = check(vec![add(&[])]);
+		assert!(matches!(result, Err(UnsatError::LemmaNotImplied)));
+
+		let result = check(vec![add(&[1, 3])]);
+		assert!(matches!(result, Err(UnsatError::ProofVarOutOfRange)));
+
+		let formula = [clause(&[1, 2]), clause(&[-1, 2])];
+		let proof = vec![add(&[1])];
+		let result = check_unsat(formula.into_iter(), proof.into_iter(), 2, 2);
+		assert!(matches!(result, Err(UnsatError::LemmaNotImplied)));
+	}
+
+	#[test]
+	fn rat_rejected() {
+		// 3 is RAT on the formula as no clause contains -3
+		let formula = [clause(&[1, 2]), clause(&[-1, 2])];
+		let proof = vec![add(&[3])];
+		let result = check_unsat(formula.into_iter(), proof.into_iter(), 3, 2);
+		assert!(matches!(result, Err(UnsatError::LemmaNotImplied)));
+	}
+
+	#[test]
+	fn tautology() {
+		assert!(check(vec![add(&[1, -1]), add(&[2]), add(&[])]).is_ok());
+	}
+
+	#[test]
+	fn deletion() {
+		let proof = vec![delete(&[2, 1]), add(&[2])];
+		let result = check(proof);
+		assert!(matches!(result, Err(UnsatError::LemmaNotImplied)));
+
+		let proof = vec![add(&[2]), delete(&[1, -2]), add(&[])];
+		let result = check(proof);
+		assert!(matches!(result, Err(UnsatError::LemmaNotImplied)));
+
+		// Deleting clauses that arent present is ignored
+		let proof = vec![delete(&[1]), delete(&[3]), add(&[2]), add(&[])];
+		assert!(check(proof).is_ok());
+	}
+
+	#[test]
+	fn no_empty_clause() {
+		let result = check(vec![add(&[2])]);
+		assert!(matches!(result, Err(UnsatError::NoEmptyClause)));
+	}
+
+	#[test]
+	fn wrong_formula() {
+		let formula = [clause(&[1, 2])];
+		let result =
+			check_unsat(formula.into_iter(), Vec::new().into_iter(), 2, 2);
+		assert!(matches!(result, Err(UnsatError::WrongNumberOfClauses)));
+
+		let formula = [clause(&[1, 3])];
+		let result =
+			check_unsat(formula.into_iter(), Vec::new().into_iter(), 2, 1);
+		assert!(matches!(result, Err(UnsatError::FormulaVarOutOfRange)));
+	}
+}
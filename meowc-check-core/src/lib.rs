@@ -4,7 +4,9 @@
 )]
 extern crate creusot_contracts;
 
+pub mod check_lrat;
 pub mod check_sat;
+pub mod check_unsat;
 
 // use creusot_contracts::std::*;
 // use creusot_contracts::*;
@@ -1,32 +1,112 @@
 use anyhow::Result;
-use clap::{arg, Parser, ValueHint::FilePath};
-use meowc_check_core::check_sat::check_sat;
-use std::path::PathBuf;
+use clap::{Parser, Subcommand, ValueHint::FilePath};
+use meowc_check_core::{
+	check_lrat::check_lrat, check_sat::check_sat, check_unsat::check_unsat,
+};
+use std::{
+	fmt::{Debug, Display},
+	path::PathBuf,
+	process,
+};
 
 mod parse;
-use parse::{dimacs_iter, proof_iter};
+use parse::{dimacs_iter, drat_iter, lrat_iter, model_iter};
+
+// Exit codes for verified results, matching the SAT competition codes for
+// the answer that was certified. Anything not verified exits with 0,
+// including certificates that fail to parse
+const EXIT_SAT: i32 = 10;
+const EXIT_UNSAT: i32 = 20;
 
 #[derive(Parser, Debug)]
 struct Args {
+	#[command(subcommand)]
+	command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+	/// Check a model of a satisfiable formula
+	Sat(Files),
+	/// Check a DRAT or RUP proof of unsatisfiability, in text or binary
+	Drat(Files),
+	/// Check an LRAT proof of unsatisfiability, in text or binary
+	Lrat(Files),
+}
+
+#[derive(clap::Args, Debug)]
+struct Files {
 	#[arg(value_hint = FilePath)]
 	dimacs_file: PathBuf,
 	#[arg(value_hint = FilePath)]
 	proof_file: PathBuf,
 }
 
-fn main() -> Result<()> {
-	let args = Args::parse();
+fn not_verified(reason: impl Display) -> i32 {
+	println!("c {}", reason);
+	println!("s NOT VERIFIED");
+	0
+}
 
-	println!("c Checking SAT proof");
-	let (dimacs, max_var, clauses) = dimacs_iter(args.dimacs_file)?;
-	let proof = proof_iter(args.proof_file)?;
-	match check_sat(dimacs, proof, max_var, clauses) {
-		Ok(()) => println!("s VERIFIED"),
-		Err(e) => {
-			println!("c {:?}", e);
-			println!("s NOT VERIFIED");
+fn report<E: Debug>(result: Result<(), E>, exit_code: i32) -> i32 {
+	match result {
+		Ok(()) => {
+			println!("s VERIFIED");
+			exit_code
 		}
+		Err(e) => not_verified(format_args!("{:?}", e)),
 	}
+}
+
+// Reading the formula stops at its first parse error, which is reported
+// rather than the result of checking the clauses read before it
+
+fn check_model(files: Files) -> Result<i32> {
+	let (mut dimacs, max_var, clauses) = dimacs_iter(files.dimacs_file)?;
+	let result = match model_iter(files.proof_file)? {
+		Ok(model) => check_sat(dimacs.by_ref(), model, max_var, clauses),
+		Err(e) => return Ok(not_verified(e)),
+	};
+	dimacs.finish()?;
+	Ok(report(result, EXIT_SAT))
+}
+
+fn check_drat(files: Files) -> Result<i32> {
+	let (mut dimacs, max_var, clauses) = dimacs_iter(files.dimacs_file)?;
+	let proof = drat_iter(files.proof_file)?;
+	let result = check_unsat(dimacs.by_ref(), proof, max_var, clauses);
+	dimacs.finish()?;
+	Ok(report(result, EXIT_UNSAT))
+}
+
+fn check_lrat_proof(files: Files) -> Result<i32> {
+	let (mut dimacs, max_var, clauses) = dimacs_iter(files.dimacs_file)?;
+	let proof = lrat_iter(files.proof_file)?;
+	let result = check_lrat(dimacs.by_ref(), proof, max_var, clauses);
+	dimacs.finish()?;
+	Ok(report(result, EXIT_UNSAT))
+}
+
+fn main() {
+	let args = Args::parse();
+
+	let result = match args.command {
+		Command::Sat(files) => {
+			println!("c Checking SAT proof");
+			check_model(files)
+		}
+		Command::Drat(files) => {
+			println!("c Checking DRAT proof");
+			check_drat(files)
+		}
+		Command::Lrat(files) => {
+			println!("c Checking LRAT proof");
+			check_lrat_proof(files)
+		}
+	};
 
-	Ok(())
+	// Certificates that cant be read or parsed are not verified either
+	let exit_code =
+		result.unwrap_or_else(|e| not_verified(format_args!("{:#}", e)));
+	process::exit(exit_code)
 }
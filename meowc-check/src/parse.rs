@@ -1,59 +1,698 @@
-use anyhow::Result;
-use meowc_check_core::check_sat::{Clause, Lit, RawLit, Var};
+use anyhow::{anyhow, bail, Context, Result};
+use meowc_check_core::{
+	check_lrat::{ClauseId, LratStep},
+	check_sat::{Clause, Lit, RawLit, Var},
+	check_unsat::ProofStep,
+};
+use meowc_sat::compression;
 use std::{
-	fs::File,
-	io::{BufRead, BufReader},
+	error, fmt,
+	io::{BufRead, Read},
 	path::Path,
+	str,
 };
 
+/// Position of a token in a DIMACS file, counting from 1
+#[derive(Debug, Clone, Copy)]
+pub struct Position {
+	pub line: usize,
+	pub column: usize,
+}
+
+#[derive(Debug)]
+pub enum DimacsErrorKind {
+	MissingHeader,
+	InvalidHeader,
+	InvalidLiteral(String),
+	UnterminatedClause,
+}
+
+#[derive(Debug)]
+pub struct DimacsError {
+	pub position: Position,
+	pub kind: DimacsErrorKind,
+}
+
+impl DimacsError {
+	fn new(position: Position, kind: DimacsErrorKind) -> Self {
+		Self { position, kind }
+	}
+}
+
+impl fmt::Display for DimacsError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"line {}, column {}: ",
+			self.position.line, self.position.column
+		)?;
+		match &self.kind {
+			DimacsErrorKind::MissingHeader => {
+				write!(f, "expected a p cnf header")
+			}
+			DimacsErrorKind::InvalidHeader => write!(f, "invalid p cnf header"),
+			DimacsErrorKind::InvalidLiteral(token) => {
+				write!(f, "invalid literal {:?}", token)
+			}
+			DimacsErrorKind::UnterminatedClause => {
+				write!(f, "clause is not terminated by 0")
+			}
+		}
+	}
+}
+
+impl error::Error for DimacsError {}
+
+/// Splits a DIMACS file into whitespace separated tokens a line at a time,
+/// dropping comments. A comment runs from a token starting with c to the end
+/// of its line, so can appear anywhere
+struct Tokenizer<R> {
+	reader: R,
+	line: String,
+	line_number: usize,
+	offset: usize,
+}
+
+impl<R: BufRead> Tokenizer<R> {
+	fn new(reader: R) -> Self {
+		Self {
+			reader,
+			line: String::new(),
+			line_number: 0,
+			offset: 0,
+		}
+	}
+
+	/// Position just past the last token read, used for errors at the end of
+	/// the file
+	fn position(&self) -> Position {
+		Position {
+			line: self.line_number.max(1),
+			column: self.offset + 1,
+		}
+	}
+
+	fn next_token(&mut self) -> Result<Option<(&str, Position)>> {
+		loop {
+			let rest = &self.line[self.offset..];
+			let start = self.offset + rest.len() - rest.trim_start().len();
+			if start < self.line.len() && !self.line[start..].starts_with('c') {
+				let end = self.line[start..]
+					.find(char::is_whitespace)
+					.map_or(self.line.len(), |len| start + len);
+				self.offset = end;
+
+				let position = Position {
+					line: self.line_number,
+					column: start + 1,
+				};
+				return Ok(Some((&self.line[start..end], position)));
+			}
+
+			self.line.clear();
+			self.offset = 0;
+			if self.reader.read_line(&mut self.line)? == 0 {
+				return Ok(None);
+			}
+			self.line_number += 1;
+		}
+	}
+
+	/// Reads the rest of a header line after its p, which is parsed on its
+	/// own as "cnf" would otherwise be taken as a comment
+	fn header(&mut self, position: Position) -> Result<(Var, usize)> {
+		let fields: Vec<&str> =
+			self.line[self.offset..].split_whitespace().collect();
+		self.offset = self.line.len();
+
+		let invalid =
+			|| DimacsError::new(position, DimacsErrorKind::InvalidHeader);
+		match fields[..] {
+			["cnf", variables, clauses] => Ok((
+				variables.parse().map_err(|_| invalid())?,
+				clauses.parse().map_err(|_| invalid())?,
+			)),
+			_ => Err(invalid().into()),
+		}
+	}
+}
+
+/// Reads the header of a DIMACS file, returning its clauses to be read as
+/// they are needed along with the number of variables and clauses given in
+/// the header
 pub fn dimacs_iter<P: AsRef<Path>>(
 	filename: P,
-) -> Result<(impl Iterator<Item = Clause>, Var, usize)> {
-	let lines = BufReader::new(File::open(filename)?).lines();
-	let mut lines = lines.skip_while(|l| l.as_ref().unwrap().starts_with("c"));
-	let problem: Vec<String> = lines
-		.next()
-		.unwrap()?
-		.split_whitespace()
-		.map(|s| s.to_string())
-		.collect();
-
-	// TODO: This is inelegant error handling
-	assert!(problem.len() == 4);
-	assert!(problem[0] == "p");
-	assert!(problem[1] == "cnf");
-	let variables = problem[2].parse()?;
-	// TODO: Check number of clauses is right
-	let clauses = problem[3].parse()?;
-
-	// TODO: really we should iterate over numbers not lines, cos clauses can
-	// take multiple lines
-	let lines = lines.map(|line| {
-		line.unwrap()
-			.split_whitespace()
-			.filter_map(|lit| {
-				let lit = lit.parse::<RawLit>().unwrap();
-				if lit == 0 {
-					return None;
-				}
-				Some(Lit::from_dimacs_unchecked(lit))
-			})
-			.collect::<Clause>()
-	});
+) -> Result<(DimacsClauses<impl BufRead>, Var, usize)> {
+	read_dimacs(compression::open(filename)?)
+}
+
+fn read_dimacs<R: BufRead>(
+	reader: R,
+) -> Result<(DimacsClauses<R>, Var, usize)> {
+	let mut tokens = Tokenizer::new(reader);
+
+	let (variables, num_clauses) = match tokens.next_token()? {
+		Some(("p", position)) => tokens.header(position)?,
+		Some((_, position)) => {
+			return Err(DimacsError::new(
+				position,
+				DimacsErrorKind::MissingHeader,
+			)
+			.into())
+		}
+		None => {
+			let position = tokens.position();
+			return Err(DimacsError::new(
+				position,
+				DimacsErrorKind::MissingHeader,
+			)
+			.into());
+		}
+	};
 
-	Ok((lines, variables, clauses))
+	let clauses = DimacsClauses {
+		tokens,
+		error: None,
+	};
+	Ok((clauses, variables, num_clauses))
 }
 
-pub fn proof_iter<P: AsRef<Path>>(
+/// Clauses of a DIMACS file, which are terminated by 0 regardless of how they
+/// are split over lines. Iteration stops at the first error, which is kept
+/// for finish to return
+pub struct DimacsClauses<R> {
+	tokens: Tokenizer<R>,
+	error: Option<anyhow::Error>,
+}
+
+impl<R: BufRead> DimacsClauses<R> {
+	fn next_clause(&mut self) -> Result<Option<Clause>> {
+		let mut clause = vec![];
+		let mut clause_start = None;
+
+		while let Some((token, position)) = self.tokens.next_token()? {
+			let lit: RawLit = token.parse().map_err(|_| {
+				let token = token.to_string();
+				DimacsError::new(
+					position,
+					DimacsErrorKind::InvalidLiteral(token),
+				)
+			})?;
+			clause_start.get_or_insert(position);
+
+			match lit {
+				0 => return Ok(Some(clause)),
+				lit => clause.push(Lit::from_dimacs_unchecked(lit)),
+			}
+		}
+
+		match clause_start {
+			Some(position) => {
+				let kind = DimacsErrorKind::UnterminatedClause;
+				Err(DimacsError::new(position, kind).into())
+			}
+			None => Ok(None),
+		}
+	}
+
+	/// The error that stopped iteration, if there was one. Clauses after the
+	/// last one read are not checked
+	pub fn finish(self) -> Result<()> {
+		match self.error {
+			Some(e) => Err(e),
+			None => Ok(()),
+		}
+	}
+}
+
+impl<R: BufRead> Iterator for DimacsClauses<R> {
+	type Item = Clause;
+
+	fn next(&mut self) -> Option<Clause> {
+		if self.error.is_some() {
+			return None;
+		}
+
+		self.next_clause().unwrap_or_else(|e| {
+			self.error = Some(e);
+			None
+		})
+	}
+}
+
+/// Reasons a solver output file does not contain a model
+#[derive(Debug)]
+pub enum ModelError {
+	ClaimedUnsatisfiable,
+	ClaimedUnknown,
+	UnknownStatus,
+	Unterminated,
+	InvalidValue(String),
+}
+
+impl fmt::Display for ModelError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ModelError::ClaimedUnsatisfiable => {
+				write!(f, "solver claimed the formula is unsatisfiable")
+			}
+			ModelError::ClaimedUnknown => {
+				write!(f, "solver did not find an answer")
+			}
+			ModelError::UnknownStatus => write!(f, "unknown solver status"),
+			ModelError::Unterminated => {
+				write!(f, "model is not terminated by 0")
+			}
+			ModelError::InvalidValue(token) => {
+				write!(f, "invalid value {:?} in model", token)
+			}
+		}
+	}
+}
+
+impl error::Error for ModelError {}
+
+/// Reads a model from SAT competition style solver output, skipping comment
+/// lines and gathering the literals of every value line up to the
+/// terminating 0. Files containing just the literals are also accepted
+pub fn model_iter<P: AsRef<Path>>(
 	filename: P,
-) -> Result<impl Iterator<Item = Lit>> {
-	Ok(BufReader::new(File::open(filename)?)
+) -> Result<Result<impl Iterator<Item = Lit>, ModelError>> {
+	read_model(compression::open(filename)?)
+}
+
+fn read_model<R: BufRead>(
+	reader: R,
+) -> Result<Result<impl Iterator<Item = Lit>, ModelError>> {
+	let mut claimed_sat = false;
+	let mut lits = vec![];
+
+	for line in reader.lines() {
+		let line = line?;
+		let mut tokens = line.split_whitespace();
+
+		let first = match tokens.next() {
+			None | Some("c") => continue,
+			Some("s") => {
+				match tokens.next() {
+					Some("SATISFIABLE") => claimed_sat = true,
+					Some("UNSATISFIABLE") => {
+						return Ok(Err(ModelError::ClaimedUnsatisfiable))
+					}
+					Some("UNKNOWN") => {
+						return Ok(Err(ModelError::ClaimedUnknown))
+					}
+					_ => return Ok(Err(ModelError::UnknownStatus)),
+				}
+				continue;
+			}
+			Some("v") => None,
+			first => first,
+		};
+
+		for value in first.into_iter().chain(tokens) {
+			match value.parse() {
+				Ok(0) => return Ok(Ok(lits.into_iter())),
+				Ok(lit) => lits.push(Lit::from_dimacs_unchecked(lit)),
+				Err(_) => {
+					let value = value.to_string();
+					return Ok(Err(ModelError::InvalidValue(value)));
+				}
+			}
+		}
+	}
+
+	// Plain lists of literals may leave out the 0, solver output may not
+	if claimed_sat {
+		return Ok(Err(ModelError::Unterminated));
+	}
+
+	Ok(Ok(lits.into_iter()))
+}
+
+// Bytes at the start of a proof that are checked for binary data
+const BINARY_PREFIX: usize = 10;
+
+/// Like drat-trim, a proof is taken to be binary when its first few bytes
+/// contain any that are neither printable nor whitespace, which text proofs
+/// never have. Checking only the first byte or two misreads binary proofs
+/// whose literals happen to encode as whitespace
+fn is_binary(proof: &[u8]) -> bool {
+	proof.iter().take(BINARY_PREFIX).any(|&byte| {
+		!byte.is_ascii_graphic()
+			&& !matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+	})
+}
+
+/// Numbers of a text proof in order, skipping comment lines. Deletion markers
+/// are returned as None
+fn text_tokens(proof: &str) -> impl Iterator<Item = Result<Option<i64>>> + '_ {
+	proof
 		.lines()
-		.flat_map(|line| {
-			line.unwrap()
-				.split_whitespace()
-				.map(|lit| Lit::from_dimacs_unchecked(lit.parse().unwrap()))
-				// TODO: This seems nasty - can it be improved
-				.collect::<Vec<_>>()
-		}))
+		.filter(|line| !line.starts_with('c'))
+		.flat_map(|line| line.split_whitespace())
+		.map(|token| match token {
+			"d" => Ok(None),
+			_ => Ok(Some(token.parse().with_context(|| {
+				format!("Invalid number {:?} in proof", token)
+			})?)),
+		})
+}
+
+/// Reads text proof numbers up to the next 0
+fn read_text_list<I>(tokens: &mut I) -> Result<Vec<i64>>
+where
+	I: Iterator<Item = Result<Option<i64>>>,
+{
+	let mut list = vec![];
+	loop {
+		match tokens.next() {
+			Some(token) => match token? {
+				Some(0) => return Ok(list),
+				Some(value) => list.push(value),
+				None => bail!("Unexpected deletion in LRAT step"),
+			},
+			None => bail!("Unexpected end of LRAT proof"),
+		}
+	}
+}
+
+fn read_varint(proof: &[u8], pos: &mut usize) -> Result<u64> {
+	let mut value = 0;
+	let mut shift = 0;
+
+	loop {
+		let byte = *proof
+			.get(*pos)
+			.ok_or_else(|| anyhow!("Unexpected end of binary proof"))?;
+		*pos += 1;
+
+		if shift >= 64 {
+			bail!("Number too large in binary proof");
+		}
+		value |= ((byte & 0x7f) as u64) << shift;
+		shift += 7;
+
+		if byte & 0x80 == 0 {
+			return Ok(value);
+		}
+	}
+}
+
+/// Reads a zero terminated list of binary encoded numbers, which map 2 * n
+/// to n and 2 * n + 1 to -n
+fn read_binary_list(proof: &[u8], pos: &mut usize) -> Result<Vec<i64>> {
+	let mut list = vec![];
+	loop {
+		let encoded = read_varint(proof, pos)?;
+		if encoded == 0 {
+			return Ok(list);
+		}
+
+		let value = (encoded >> 1) as i64;
+		list.push(if encoded & 1 == 1 { -value } else { value });
+	}
+}
+
+fn to_clause(lits: &[i64]) -> Result<Clause> {
+	lits.iter()
+		.map(|&lit| Ok(Lit::from_dimacs_unchecked(RawLit::try_from(lit)?)))
+		.collect()
+}
+
+fn to_ids(ids: &[i64]) -> Result<Vec<ClauseId>> {
+	ids.iter()
+		.map(|&id| {
+			if id < 0 {
+				bail!("RAT hints are not supported");
+			}
+			Ok(id as ClauseId)
+		})
+		.collect()
+}
+
+pub fn drat_iter<P: AsRef<Path>>(
+	filename: P,
+) -> Result<impl Iterator<Item = ProofStep>> {
+	let mut proof = vec![];
+	compression::open(filename)?.read_to_end(&mut proof)?;
+	let mut steps = vec![];
+
+	if is_binary(&proof) {
+		let mut pos = 0;
+		while pos < proof.len() {
+			let kind = proof[pos];
+			pos += 1;
+
+			let clause = to_clause(&read_binary_list(&proof, &mut pos)?)?;
+			steps.push(match kind {
+				b'a' => ProofStep::Add(clause),
+				b'd' => ProofStep::Delete(clause),
+				_ => bail!("Unknown step kind {} in binary proof", kind),
+			});
+		}
+	} else {
+		let mut deletion = false;
+		let mut lits = vec![];
+
+		for token in text_tokens(str::from_utf8(&proof)?) {
+			match token? {
+				None => deletion = true,
+				Some(0) => {
+					let clause = to_clause(&lits)?;
+					lits.clear();
+					steps.push(if deletion {
+						ProofStep::Delete(clause)
+					} else {
+						ProofStep::Add(clause)
+					});
+					deletion = false;
+				}
+				Some(lit) => lits.push(lit),
+			}
+		}
+	}
+
+	Ok(steps.into_iter())
+}
+
+pub fn lrat_iter<P: AsRef<Path>>(
+	filename: P,
+) -> Result<impl Iterator<Item = LratStep>> {
+	let mut proof = vec![];
+	compression::open(filename)?.read_to_end(&mut proof)?;
+	let mut steps = vec![];
+
+	if is_binary(&proof) {
+		let mut pos = 0;
+		while pos < proof.len() {
+			let kind = proof[pos];
+			pos += 1;
+
+			steps.push(match kind {
+				b'a' => {
+					let id = read_varint(&proof, &mut pos)? >> 1;
+					let clause =
+						to_clause(&read_binary_list(&proof, &mut pos)?)?;
+					let hints = to_ids(&read_binary_list(&proof, &mut pos)?)?;
+					LratStep::Add { id, clause, hints }
+				}
+				b'd' => LratStep::Delete(to_ids(&read_binary_list(
+					&proof, &mut pos,
+				)?)?),
+				_ => bail!("Unknown step kind {} in binary proof", kind),
+			});
+		}
+	} else {
+		let mut tokens = text_tokens(str::from_utf8(&proof)?).peekable();
+
+		while let Some(token) = tokens.next() {
+			let id = match token? {
+				Some(id) if id > 0 => id as ClauseId,
+				_ => bail!("Expected a step ID in LRAT proof"),
+			};
+
+			// The ID of a deletion is that of the last clause added, which
+			// isnt needed to check it
+			if let Some(Ok(None)) = tokens.peek() {
+				tokens.next();
+				steps.push(LratStep::Delete(to_ids(&read_text_list(
+					&mut tokens,
+				)?)?));
+			} else {
+				let clause = to_clause(&read_text_list(&mut tokens)?)?;
+				let hints = to_ids(&read_text_list(&mut tokens)?)?;
+				steps.push(LratStep::Add { id, clause, hints });
+			}
+		}
+	}
+
+	Ok(steps.into_iter())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		is_binary, read_dimacs, read_model, DimacsError, DimacsErrorKind,
+		ModelError,
+	};
+	use anyhow::Result;
+	use meowc_check_core::check_sat::{Clause, Lit};
+
+	fn clauses(clauses: &[&[i32]]) -> Vec<Clause> {
+		clauses
+			.iter()
+			.map(|lits| {
+				lits.iter()
+					.map(|&lit| Lit::from_dimacs_unchecked(lit))
+					.collect()
+			})
+			.collect()
+	}
+
+	fn parse_dimacs(dimacs: &str) -> Result<Vec<Clause>> {
+		let (mut reader, _, _) = read_dimacs(dimacs.as_bytes())?;
+		let clauses = reader.by_ref().collect();
+		reader.finish()?;
+		Ok(clauses)
+	}
+
+	/// Kind, line and column of the error reading dimacs
+	fn dimacs_error(dimacs: &str) -> (DimacsErrorKind, usize, usize) {
+		let e = parse_dimacs(dimacs).err().unwrap();
+		let e = e.downcast::<DimacsError>().unwrap();
+		(e.kind, e.position.line, e.position.column)
+	}
+
+	#[test]
+	fn dimacs_clauses() {
+		let (mut reader, variables, num_clauses) =
+			read_dimacs("c comment\np cnf 3 2\n1 -2 0\n".as_bytes()).unwrap();
+		assert_eq!((variables, num_clauses), (3, 2));
+		assert!(reader.next() == Some(clauses(&[&[1, -2]]).remove(0)));
+		assert!(reader.next().is_none());
+		assert!(reader.finish().is_ok());
+	}
+
+	#[test]
+	fn multi_line_clauses() {
+		let dimacs = "p cnf 3 2\n1\n-2\n\n3 0\n-1\n0\n";
+		let expected = clauses(&[&[1, -2, 3], &[-1]]);
+		assert!(parse_dimacs(dimacs).unwrap() == expected);
+	}
+
+	#[test]
+	fn clauses_on_one_line() {
+		let dimacs = "p cnf 2 4\n1 0 -2 0 0 1 2 0\n";
+		let expected = clauses(&[&[1], &[-2], &[], &[1, 2]]);
+		assert!(parse_dimacs(dimacs).unwrap() == expected);
+	}
+
+	#[test]
+	fn comments() {
+		let dimacs = "c before\np cnf 2 2\n1 c within a clause\n-2 0\n\
+			c between\n2 0 c after\n";
+		let expected = clauses(&[&[1, -2], &[2]]);
+		assert!(parse_dimacs(dimacs).unwrap() == expected);
+	}
+
+	#[test]
+	fn dimacs_error_positions() {
+		assert!(matches!(
+			dimacs_error(""),
+			(DimacsErrorKind::MissingHeader, 1, 1)
+		));
+		assert!(matches!(
+			dimacs_error("c comment\n1 0\n"),
+			(DimacsErrorKind::MissingHeader, 2, 1)
+		));
+		assert!(matches!(
+			dimacs_error("p cnf x 1\n"),
+			(DimacsErrorKind::InvalidHeader, 1, 1)
+		));
+		assert!(matches!(
+			dimacs_error("p cnf 2 1 3\n"),
+			(DimacsErrorKind::InvalidHeader, 1, 1)
+		));
+		assert!(matches!(
+			dimacs_error("p cnf 2 2\n1 0\n  2 x 0\n"),
+			(DimacsErrorKind::InvalidLiteral(token), 3, 5) if token == "x"
+		));
+		assert!(matches!(
+			dimacs_error("p cnf 2 2\n1 0\n -2\n2\n"),
+			(DimacsErrorKind::UnterminatedClause, 3, 2)
+		));
+	}
+
+	#[test]
+	fn clauses_stop_at_error() {
+		let (mut reader, _, _) =
+			read_dimacs("p cnf 2 3\n1 0\n-2 x 0\n2 0\n".as_bytes()).unwrap();
+		assert!(reader.next().is_some());
+		assert!(reader.next().is_none());
+		assert!(reader.next().is_none());
+		assert!(reader.finish().is_err());
+	}
+
+	fn model(output: &str) -> Result<Vec<Lit>, ModelError> {
+		read_model(output.as_bytes())
+			.unwrap()
+			.map(|lits| lits.collect())
+	}
+
+	#[test]
+	fn solver_output() {
+		let output = "c banner\ns SATISFIABLE\nv 1 -2\nc between\nv 3 0\n";
+		let expected = clauses(&[&[1, -2, 3]]).remove(0);
+		assert!(model(output).unwrap() == expected);
+
+		// Nothing after the terminating 0 is read
+		let output = "s SATISFIABLE\nv -1 0\nv x\n";
+		assert!(model(output).unwrap() == clauses(&[&[-1]]).remove(0));
+	}
+
+	#[test]
+	fn plain_literals() {
+		let expected = clauses(&[&[1, -2, 3]]).remove(0);
+		assert!(model("1 -2\n3 0\n").unwrap() == expected);
+		assert!(model("1 -2 3\n").unwrap() == expected);
+	}
+
+	#[test]
+	fn not_models() {
+		assert!(matches!(
+			model("c banner\ns UNSATISFIABLE\n"),
+			Err(ModelError::ClaimedUnsatisfiable)
+		));
+		assert!(matches!(
+			model("s UNKNOWN\n"),
+			Err(ModelError::ClaimedUnknown)
+		));
+		assert!(matches!(
+			model("s SATISFIED\nv 1 0\n"),
+			Err(ModelError::UnknownStatus)
+		));
+		assert!(matches!(
+			model("s SATISFIABLE\nv 1 -2\n"),
+			Err(ModelError::Unterminated)
+		));
+		assert!(matches!(
+			model("s SATISFIABLE\nv 1 -2 x 0\n"),
+			Err(ModelError::InvalidValue(value)) if value == "x"
+		));
+	}
+
+	#[test]
+	fn binary_deletion_of_whitespace_literal() {
+		// Deletes the clause (5 6 7), whose first literal encodes as \n
+		assert!(is_binary(&[0x64, 0x0a, 0x0c, 0x0e, 0x00, 0x61]));
+	}
+
+	#[test]
+	fn text_proofs() {
+		assert!(!is_binary(b"d 1 -2 0\n3 0\n"));
+		assert!(!is_binary(b"c comment\n1 2 0\n"));
+		assert!(!is_binary(b"4 1 -2 0 1 3 0\r\n5 d 1 0\n"));
+		assert!(!is_binary(b""));
+	}
 }
@@ -1,29 +1,45 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use meowc_sat::dimacs_cnf::parse_dimacs;
+use meowc_sat::{
+	config::{Config, Heuristic, SearchMode},
+	dimacs_cnf::parse_dimacs,
+};
 use std::{fs, time::Duration};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
-	let mut group = c.benchmark_group("cnfs");
-	group
-		.sample_size(40)
-		.significance_level(0.08)
-		.noise_threshold(0.05)
-		.warm_up_time(Duration::from_millis(500))
-		.measurement_time(Duration::from_secs(3));
-
 	const CNFS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/cnfs");
-	for file in fs::read_dir(CNFS_PATH).unwrap() {
-		let file = file.unwrap();
+	const HEURISTICS: [(&str, Heuristic); 2] =
+		[("vsids", Heuristic::Vsids), ("vmtf", Heuristic::Vmtf)];
 
-		let dimacs = fs::read_to_string(file.path()).unwrap();
-		let solver = parse_dimacs(&dimacs).unwrap();
+	for (name, heuristic) in HEURISTICS {
+		let mut group = c.benchmark_group(format!("cnfs-{}", name));
+		group
+			.sample_size(40)
+			.significance_level(0.08)
+			.noise_threshold(0.05)
+			.warm_up_time(Duration::from_millis(500))
+			.measurement_time(Duration::from_secs(3));
 
-		group.bench_function(file.file_name().to_str().unwrap(), |b| {
-			b.iter(|| black_box(solver.clone()).solve())
-		});
-	}
+		let config = Config {
+			mode: SearchMode::Stable,
+			stable_heuristic: heuristic,
+			mode_switch_interval: None,
+			..Default::default()
+		};
+
+		for file in fs::read_dir(CNFS_PATH).unwrap() {
+			let file = file.unwrap();
 
-	group.finish();
+			let dimacs = fs::read_to_string(file.path()).unwrap();
+			let mut solver = parse_dimacs(&dimacs).unwrap();
+			solver.set_config(config.clone());
+
+			group.bench_function(file.file_name().to_str().unwrap(), |b| {
+				b.iter(|| black_box(solver.clone()).solve())
+			});
+		}
+
+		group.finish();
+	}
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,29 @@
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use std::{
+	fs::File,
+	io::{self, BufRead, BufReader},
+	path::Path,
+};
+use xz2::read::XzDecoder;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+
+/// Opens a file for reading, decompressing it on the fly if it starts with
+/// the magic bytes of gzip, xz or bzip2
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn BufRead>> {
+	let mut file = BufReader::new(File::open(path)?);
+	let magic = file.fill_buf()?;
+
+	Ok(if magic.starts_with(GZIP_MAGIC) {
+		Box::new(BufReader::new(MultiGzDecoder::new(file)))
+	} else if magic.starts_with(XZ_MAGIC) {
+		Box::new(BufReader::new(XzDecoder::new_multi_decoder(file)))
+	} else if magic.starts_with(BZIP2_MAGIC) {
+		Box::new(BufReader::new(MultiBzDecoder::new(file)))
+	} else {
+		Box::new(file)
+	})
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+	/// Pick the variable with the highest decaying conflict activity
+	Vsids,
+	/// Pick the variable most recently involved in a conflict
+	Vmtf,
+}
+
+/// Focused mode aims at quickly finding short refutations, stable mode at
+/// making steady progress towards a model. Each mode has its own decision
+/// heuristic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+	Focused,
+	Stable,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+	/// Restart when recently learnt clauses have much higher LBDs than usual,
+	/// unless the trail is much longer than usual, as in Glucose
+	Glucose,
+	/// Restart at intervals following the Luby sequence
+	Luby,
+	/// Restart at geometrically growing intervals
+	Geometric,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+	/// Factor VSIDS activities are multiplied by after each conflict. Values
+	/// closer to 1 make the heuristic slower to forget older conflicts
+	pub vsids_decay: f64,
+
+	/// Mode to start solving in
+	pub mode: SearchMode,
+	pub focused_heuristic: Heuristic,
+	pub stable_heuristic: Heuristic,
+	pub focused_restarts: RestartPolicy,
+	pub stable_restarts: RestartPolicy,
+
+	/// Number of conflicts before first switching to the other search mode.
+	/// Each phase after that is twice as long as the one before. Without an
+	/// interval the solver stays in its starting mode
+	pub mode_switch_interval: Option<u64>,
+
+	/// Also minimize learnt clauses using binary clauses containing the UIP
+	pub binary_minimization: bool,
+
+	/// Remove subsumed clauses and strengthen clauses by self-subsuming
+	/// resolution, before the first search and periodically among learnt
+	/// clauses
+	pub subsumption: bool,
+	/// Eliminate variables by resolution before the first search
+	pub elimination: bool,
+	/// Remove blocked clauses before the first search
+	pub blocked_elimination: bool,
+	/// Also remove clauses that are blocked once extended by covered literal
+	/// addition. Only applies with blocked_elimination
+	pub covered_elimination: bool,
+	/// Probe for failed literals and substitute equivalent literals, before
+	/// the first search and periodically after that
+	pub probing: bool,
+
+	/// Seed for randomizing the initial phases of variables. Without one
+	/// every variable is first decided false
+	pub seed: Option<u64>,
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			vsids_decay: 0.95,
+
+			mode: SearchMode::Focused,
+			focused_heuristic: Heuristic::Vmtf,
+			stable_heuristic: Heuristic::Vsids,
+			focused_restarts: RestartPolicy::Glucose,
+			stable_restarts: RestartPolicy::Luby,
+
+			mode_switch_interval: Some(1000),
+
+			binary_minimization: true,
+
+			subsumption: true,
+			elimination: true,
+			blocked_elimination: true,
+			covered_elimination: false,
+			probing: true,
+
+			seed: None,
+		}
+	}
+}
+
+/// Limits on the resources a single call to solve may use before giving up
+/// with an unknown result. Nothing is limited by default
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+	pub conflicts: Option<u64>,
+	pub propagations: Option<u64>,
+	pub decisions: Option<u64>,
+	pub time: Option<Duration>,
+}
@@ -2,42 +2,320 @@ use crate::{
 	solver::Solver,
 	types::{Clause, Lit},
 };
-use pest::{error::Error, Parser};
+use pest::{iterators::Pair, Parser};
+use std::{error, fmt, io};
+
+mod reader;
+pub use reader::read_dimacs;
 
 #[derive(Parser)]
 #[grammar = "dimacs_cnf.pest"]
 struct DIMACSParser;
 
-pub fn parse_dimacs(dimacs_str: &str) -> Result<Solver, Error<Rule>> {
-	let dimacs = DIMACSParser::parse(Rule::dimacs, dimacs_str)?
-		.next()
-		.unwrap();
+// Literals are stored as twice their variable, so larger variables dont fit
+const MAX_VAR: u32 = i32::MAX as u32 >> 1;
 
-	let mut lines = dimacs
-		.into_inner()
-		.skip_while(|line| line.as_rule() == Rule::comment);
+/// In strict mode the header has to match the clauses that follow it.
+/// Benchmark files often get it slightly wrong, so lenient mode instead
+/// infers whatever the header gets wrong or leaves out from the clauses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+	#[default]
+	Strict,
+	Lenient,
+}
 
-	let num_vars = lines
-		.next()
-		.unwrap()
-		.into_inner()
-		.skip_while(|part| part.as_rule() != Rule::num_variables)
+/// Part of the input an error refers to, as a byte range along with the line
+/// and column it starts at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+	pub start: usize,
+	pub end: usize,
+	pub line: usize,
+	pub column: usize,
+}
+
+impl From<pest::Span<'_>> for Span {
+	fn from(span: pest::Span) -> Self {
+		let (line, column) = span.start_pos().line_col();
+		Self {
+			start: span.start(),
+			end: span.end(),
+			line,
+			column,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum DimacsError {
+	Io(io::Error),
+	/// The input doesnt follow the DIMACS grammar at all
+	Syntax(Box<pest::error::Error<Rule>>),
+	UnexpectedCharacter(Span),
+	MissingHeader,
+	/// The header counts arent valid numbers
+	InvalidHeader(Span),
+	/// A literal whose variable is too large to be represented
+	LiteralOverflow(Span),
+	VarOutOfRange {
+		span: Span,
+		var: u32,
+		num_vars: u32,
+	},
+	/// The header is given as the span
+	ClauseCountMismatch {
+		span: Span,
+		declared: usize,
+		found: usize,
+	},
+	UnterminatedClause(Span),
+}
+
+impl fmt::Display for DimacsError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Io(e) => write!(f, "{}", e),
+			Self::Syntax(e) => write!(f, "{}", e),
+			Self::UnexpectedCharacter(span) => {
+				write!(f, "{}:{}: unexpected character", span.line, span.column)
+			}
+			Self::MissingHeader => write!(f, "missing p cnf header"),
+			Self::InvalidHeader(span) => {
+				write!(f, "{}:{}: invalid header", span.line, span.column)
+			}
+			Self::LiteralOverflow(span) => {
+				write!(f, "{}:{}: literal is too large", span.line, span.column)
+			}
+			Self::VarOutOfRange {
+				span,
+				var,
+				num_vars,
+			} => write!(
+				f,
+				"{}:{}: variable {} is out of the declared range 1..={}",
+				span.line, span.column, var, num_vars
+			),
+			Self::ClauseCountMismatch {
+				span,
+				declared,
+				found,
+			} => write!(
+				f,
+				"{}:{}: header declares {} clauses but {} were found",
+				span.line, span.column, declared, found
+			),
+			Self::UnterminatedClause(span) => write!(
+				f,
+				"{}:{}: last clause is not terminated by 0",
+				span.line, span.column
+			),
+		}
+	}
+}
+
+impl error::Error for DimacsError {}
+
+/// Parses a DIMACS CNF file in strict mode. This builds a whole parse tree
+/// first, so read_dimacs is much faster on large inputs, but the grammar is
+/// kept as a reference to check it against
+pub fn parse_dimacs(dimacs_str: &str) -> Result<Solver, DimacsError> {
+	parse_dimacs_with_mode(dimacs_str, ParseMode::Strict)
+}
+
+pub fn parse_dimacs_with_mode(
+	dimacs_str: &str,
+	mode: ParseMode,
+) -> Result<Solver, DimacsError> {
+	let strict = mode == ParseMode::Strict;
+
+	let dimacs = DIMACSParser::parse(Rule::dimacs, dimacs_str)
+		.map_err(|e| DimacsError::Syntax(Box::new(e)))?
 		.next()
-		.unwrap()
-		.as_str()
-		.parse::<u32>()
 		.unwrap();
 
-	let mut solver = Solver::new(num_vars);
+	let mut header = None;
+	let mut clauses = vec![];
+	let mut max_var = 0;
+
+	for line in dimacs.into_inner() {
+		match line.as_rule() {
+			Rule::problem_statement => header = Some(parse_header(line)?),
+			Rule::clause => {
+				if strict && header.is_none() {
+					return Err(DimacsError::MissingHeader);
+				}
 
-	for clause in lines.filter(|line| line.as_rule() == Rule::clause) {
-		solver.add_clause(
-			&clause
-				.into_inner()
-				.map(|lit| Lit::from(lit.as_str().parse::<i32>().unwrap()))
-				.collect::<Clause>(),
-		);
+				let span = line.as_span();
+				let (clause, terminated) = parse_clause(line)?;
+				for &(lit, span) in clause.iter() {
+					let var = lit.unsigned_abs();
+					if let Some((_, num_vars, _)) = header {
+						if strict && var > num_vars {
+							let span = Span::from(span);
+							return Err(DimacsError::VarOutOfRange {
+								span,
+								var,
+								num_vars,
+							});
+						}
+					}
+					max_var = max_var.max(var);
+				}
+
+				if strict && !terminated {
+					let span = Span::from(span);
+					return Err(DimacsError::UnterminatedClause(span));
+				}
+
+				clauses.push(
+					clause
+						.into_iter()
+						.map(|(lit, _)| Lit::from(lit))
+						.collect::<Clause>(),
+				);
+			}
+			_ => (),
+		}
+	}
+
+	let num_vars = match header {
+		Some((span, num_vars, num_clauses)) => {
+			if strict && num_clauses != clauses.len() {
+				return Err(DimacsError::ClauseCountMismatch {
+					span,
+					declared: num_clauses,
+					found: clauses.len(),
+				});
+			}
+			num_vars.max(max_var)
+		}
+		None if strict => return Err(DimacsError::MissingHeader),
+		None => max_var,
+	};
+
+	let mut solver = Solver::new(num_vars);
+	for clause in clauses.iter() {
+		solver.add_clause(clause);
 	}
 
 	Ok(solver)
 }
+
+fn parse_header(header: Pair<Rule>) -> Result<(Span, u32, usize), DimacsError> {
+	let span = Span::from(header.as_span());
+
+	let mut num_vars = None;
+	let mut num_clauses = None;
+	for part in header.into_inner() {
+		match part.as_rule() {
+			Rule::num_variables => num_vars = part.as_str().parse().ok(),
+			Rule::num_clauses => num_clauses = part.as_str().parse().ok(),
+			_ => (),
+		}
+	}
+
+	match (num_vars, num_clauses) {
+		(Some(num_vars), Some(num_clauses)) if num_vars <= MAX_VAR => {
+			Ok((span, num_vars, num_clauses))
+		}
+		_ => Err(DimacsError::InvalidHeader(span)),
+	}
+}
+
+/// Literals of a clause along with where they are in the input, and whether
+/// the clause is terminated by a 0. The pest spans are only converted for
+/// errors, as finding their line and column means scanning the input
+fn parse_clause(
+	clause: Pair<Rule>,
+) -> Result<(Vec<(i32, pest::Span)>, bool), DimacsError> {
+	let mut lits = vec![];
+	let mut terminated = false;
+	for part in clause.into_inner() {
+		match part.as_rule() {
+			Rule::literal => {
+				let span = part.as_span();
+				match part.as_str().parse::<i32>() {
+					Ok(lit) if lit.unsigned_abs() <= MAX_VAR => {
+						lits.push((lit, span))
+					}
+					_ => {
+						let span = Span::from(span);
+						return Err(DimacsError::LiteralOverflow(span));
+					}
+				}
+			}
+			Rule::terminator => terminated = true,
+			_ => (),
+		}
+	}
+
+	Ok((lits, terminated))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_dimacs, read_dimacs, ParseMode};
+	use std::fs;
+
+	/// Both parsers have to either build the same formula or both fail
+	fn check_agree(dimacs: &str) {
+		let parsed = parse_dimacs(dimacs).map(|solver| solver.formula());
+		let read = read_dimacs(dimacs.as_bytes(), ParseMode::Strict)
+			.map(|solver| solver.formula());
+		match (parsed, read) {
+			(Ok(parsed), Ok(read)) => assert_eq!(parsed, read, "{:?}", dimacs),
+			(Err(_), Err(_)) => (),
+			(parsed, read) => {
+				panic!("{:?}: parsed {:?}, read {:?}", dimacs, parsed, read)
+			}
+		}
+	}
+
+	#[test]
+	fn bundled_cnfs() {
+		const CNFS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/cnfs");
+		for file in fs::read_dir(CNFS_PATH).unwrap() {
+			let dimacs = fs::read_to_string(file.unwrap().path()).unwrap();
+			assert!(parse_dimacs(&dimacs).is_ok());
+			check_agree(&dimacs);
+		}
+	}
+
+	#[test]
+	fn accepted() {
+		for dimacs in [
+			"p cnf 2 2\n1 -2 0\nc between clauses\n2 0\n",
+			"c before\np cnf 2 1\n1\nc within a clause\n-2 0\nc after",
+			"p cnf 2 2\n1 -2 00\n002 0\n",
+			"p cnf 1 4\n1 0\n0\n 0 -1 0\n",
+			"p cnf 1 1\r\n-1\t0\r\n",
+			"pcnf 1 1\n1 0",
+		] {
+			assert!(parse_dimacs(dimacs).is_ok(), "{:?}", dimacs);
+			check_agree(dimacs);
+		}
+	}
+
+	#[test]
+	fn rejected() {
+		for dimacs in [
+			"",
+			"1 0\n",
+			"p cnf 1\n1 0\n",
+			"p cnf x 1\n1 0\n",
+			"p cnf 99999999999999999999 1\n1 0\n",
+			"p cnf 1 1\n-0 0\n",
+			"p cnf 1 1\n1c 0\n",
+			"p cnf 1 1\n1 0c\n",
+			"p cnf 1 1\n2 0\n",
+			"p cnf 1 1\n1\n",
+			"p cnf 1 2\n1 0\n",
+			"p cnf 2 1\n99999999999999999999 0\n",
+			"p cnf 2 1\n1 % 0\n",
+		] {
+			assert!(parse_dimacs(dimacs).is_err(), "{:?}", dimacs);
+			check_agree(dimacs);
+		}
+	}
+}
@@ -0,0 +1,326 @@
+use super::{DimacsError, ParseMode, Span, MAX_VAR};
+use crate::{
+	solver::Solver,
+	types::{Clause, Lit},
+};
+use std::io::{ErrorKind, Read};
+
+const BUFFER_SIZE: usize = 1 << 16;
+
+/// Reads the input a buffer at a time, keeping track of where in the input
+/// it is for errors
+struct Input<R> {
+	reader: R,
+	buffer: Box<[u8]>,
+	pos: usize,
+	len: usize,
+
+	offset: usize,
+	line: usize,
+	column: usize,
+}
+
+impl<R: Read> Input<R> {
+	fn new(reader: R) -> Self {
+		Self {
+			reader,
+			buffer: vec![0; BUFFER_SIZE].into_boxed_slice(),
+			pos: 0,
+			len: 0,
+			offset: 0,
+			line: 1,
+			column: 1,
+		}
+	}
+
+	fn peek(&mut self) -> Result<Option<u8>, DimacsError> {
+		if self.pos == self.len {
+			self.len = loop {
+				match self.reader.read(&mut self.buffer) {
+					Ok(len) => break len,
+					Err(e) if e.kind() == ErrorKind::Interrupted => (),
+					Err(e) => return Err(DimacsError::Io(e)),
+				}
+			};
+			self.pos = 0;
+		}
+
+		Ok(self.buffer[..self.len].get(self.pos).copied())
+	}
+
+	/// Moves past the byte returned by the last peek
+	fn bump(&mut self) {
+		if self.buffer[self.pos] == b'\n' {
+			self.line += 1;
+			self.column = 1;
+		} else {
+			self.column += 1;
+		}
+		self.pos += 1;
+		self.offset += 1;
+	}
+
+	/// Empty span at the current position, to be extended over a token
+	fn span(&self) -> Span {
+		Span {
+			start: self.offset,
+			end: self.offset,
+			line: self.line,
+			column: self.column,
+		}
+	}
+
+	fn unexpected(&self) -> DimacsError {
+		let span = self.span();
+		DimacsError::UnexpectedCharacter(Span {
+			end: span.start + 1,
+			..span
+		})
+	}
+
+	fn skip_line(&mut self) -> Result<(), DimacsError> {
+		while let Some(byte) = self.peek()? {
+			self.bump();
+			if byte == b'\n' {
+				break;
+			}
+		}
+		Ok(())
+	}
+
+	/// Skips whitespace and comments, returning the next byte
+	fn skip_blank(&mut self) -> Result<Option<u8>, DimacsError> {
+		loop {
+			match self.peek()? {
+				Some(b' ' | b'\t' | b'\r' | b'\n') => self.bump(),
+				Some(b'c') => self.skip_line()?,
+				byte => return Ok(byte),
+			}
+		}
+	}
+
+	/// Skips whitespace within a line
+	fn skip_spaces(&mut self) -> Result<Option<u8>, DimacsError> {
+		loop {
+			match self.peek()? {
+				Some(b' ' | b'\t') => self.bump(),
+				byte => return Ok(byte),
+			}
+		}
+	}
+
+	/// Reads a number, which has to be followed by whitespace or the end of
+	/// the input. Numbers too large for a u64 give the error made by overflow
+	/// from the span of their digits, once they have all been read
+	fn number(
+		&mut self,
+		overflow: impl FnOnce(Span) -> DimacsError,
+	) -> Result<Option<u64>, DimacsError> {
+		let mut span = self.span();
+		let mut value: Option<u64> = Some(0);
+		let mut digits = 0;
+		while let Some(byte @ b'0'..=b'9') = self.peek()? {
+			value = value
+				.and_then(|value| value.checked_mul(10))
+				.and_then(|value| value.checked_add((byte - b'0') as u64));
+			digits += 1;
+			self.bump();
+		}
+
+		match self.peek()? {
+			None | Some(b' ' | b'\t' | b'\r' | b'\n') if digits > 0 => {
+				match value {
+					Some(value) => Ok(Some(value)),
+					None => {
+						span.end = self.offset;
+						Err(overflow(span))
+					}
+				}
+			}
+			_ => Ok(None),
+		}
+	}
+
+	/// Reads a p cnf header, starting at the p
+	fn header(&mut self) -> Result<(Span, u32, usize), DimacsError> {
+		let mut span = self.span();
+		self.bump();
+
+		// The problem type is optional in the pest grammar too
+		if self.skip_spaces()? == Some(b'c') {
+			for &expected in b"cnf" {
+				if self.peek()? != Some(expected) {
+					return Err(self.unexpected());
+				}
+				self.bump();
+			}
+		}
+
+		// Counts that overflow make the header up to them invalid
+		let overflow = |digits: Span| {
+			DimacsError::InvalidHeader(Span {
+				end: digits.end,
+				..span.clone()
+			})
+		};
+		self.skip_spaces()?;
+		let num_vars = self.number(overflow)?;
+		self.skip_spaces()?;
+		let num_clauses = self.number(overflow)?;
+		span.end = self.offset;
+
+		let num_vars = num_vars.filter(|&num_vars| num_vars <= MAX_VAR as u64);
+		let num_clauses =
+			num_clauses.and_then(|num_clauses| num_clauses.try_into().ok());
+		match (num_vars, num_clauses) {
+			(Some(num_vars), Some(num_clauses)) => {
+				Ok((span, num_vars as u32, num_clauses))
+			}
+			_ => Err(DimacsError::InvalidHeader(span)),
+		}
+	}
+
+	fn literal(&mut self) -> Result<(i32, Span), DimacsError> {
+		let mut span = self.span();
+
+		let negative = self.peek()? == Some(b'-');
+		if negative {
+			self.bump();
+		}
+
+		let overflow = |digits: Span| {
+			DimacsError::LiteralOverflow(Span {
+				end: digits.end,
+				..span.clone()
+			})
+		};
+		let value = match self.number(overflow)? {
+			Some(value) => value,
+			None => return Err(self.unexpected()),
+		};
+		span.end = self.offset;
+
+		if negative && value == 0 {
+			return Err(DimacsError::UnexpectedCharacter(span));
+		}
+		if value > MAX_VAR as u64 {
+			return Err(DimacsError::LiteralOverflow(span));
+		}
+
+		Ok((
+			if negative {
+				-(value as i32)
+			} else {
+				value as i32
+			},
+			span,
+		))
+	}
+}
+
+/// Reads a DIMACS CNF file straight into a solver a buffer at a time, without
+/// building a parse tree first. It accepts the same inputs as parse_dimacs,
+/// which the tests check on the bundled CNFs.
+///
+/// Clauses are added to the solver as they are read when the header can be
+/// trusted for the number of variables. In lenient mode they are kept in a
+/// flat buffer until the whole input has been read instead
+pub fn read_dimacs<R: Read>(
+	reader: R,
+	mode: ParseMode,
+) -> Result<Solver, DimacsError> {
+	let strict = mode == ParseMode::Strict;
+	let mut input = Input::new(reader);
+
+	let header = match input.skip_blank()? {
+		Some(b'p') => Some(input.header()?),
+		_ if strict => return Err(DimacsError::MissingHeader),
+		_ => None,
+	};
+
+	let mut solver = match header {
+		Some((_, num_vars, _)) if strict => Some(Solver::new(num_vars)),
+		_ => None,
+	};
+	let mut buffered = vec![];
+	let mut clause_ends = vec![];
+
+	let mut clause: Clause = vec![];
+	let mut clause_span = input.span();
+	let mut num_clauses = 0;
+	let mut max_var = 0;
+
+	while input.skip_blank()?.is_some() {
+		let (lit, span) = input.literal()?;
+		if clause.is_empty() {
+			clause_span = span.clone();
+		}
+
+		if lit == 0 {
+			match solver.as_mut() {
+				Some(solver) => solver.add_clause(&clause),
+				None => {
+					buffered.extend_from_slice(&clause);
+					clause_ends.push(buffered.len());
+				}
+			}
+			clause.clear();
+			num_clauses += 1;
+			continue;
+		}
+
+		let var = lit.unsigned_abs();
+		if let Some((_, num_vars, _)) = header {
+			if strict && var > num_vars {
+				return Err(DimacsError::VarOutOfRange {
+					span,
+					var,
+					num_vars,
+				});
+			}
+		}
+		max_var = max_var.max(var);
+		clause.push(Lit::from(lit));
+	}
+
+	if !clause.is_empty() {
+		if strict {
+			clause_span.end = input.offset;
+			return Err(DimacsError::UnterminatedClause(clause_span));
+		}
+
+		buffered.extend_from_slice(&clause);
+		clause_ends.push(buffered.len());
+		num_clauses += 1;
+	}
+
+	match (solver, header) {
+		(Some(solver), Some((span, _, declared))) => {
+			if declared != num_clauses {
+				return Err(DimacsError::ClauseCountMismatch {
+					span,
+					declared,
+					found: num_clauses,
+				});
+			}
+			Ok(solver)
+		}
+		(_, header) => {
+			let num_vars = match header {
+				Some((_, num_vars, _)) => num_vars.max(max_var),
+				None => max_var,
+			};
+
+			let mut solver = Solver::new(num_vars);
+			let mut start = 0;
+			for &end in clause_ends.iter() {
+				clause.clear();
+				clause.extend_from_slice(&buffered[start..end]);
+				solver.add_clause(&clause);
+				start = end;
+			}
+
+			Ok(solver)
+		}
+	}
+}
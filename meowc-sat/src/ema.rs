@@ -0,0 +1,34 @@
+/// Exponential moving average. The bias towards the initial value of 0 is
+/// corrected as in ADAM, so early values are close to the plain average
+#[derive(Clone)]
+pub struct Ema {
+	alpha: f64,
+	biased: f64,
+	// (1 - alpha)^n after n updates
+	decay: f64,
+}
+
+impl Ema {
+	pub fn new(alpha: f64) -> Self {
+		Self {
+			alpha,
+			biased: 0.0,
+			decay: 1.0,
+		}
+	}
+
+	#[inline]
+	pub fn update(&mut self, value: f64) {
+		self.biased += self.alpha * (value - self.biased);
+		self.decay *= 1.0 - self.alpha;
+	}
+
+	#[inline]
+	pub fn value(&self) -> f64 {
+		if self.decay == 1.0 {
+			0.0
+		} else {
+			self.biased / (1.0 - self.decay)
+		}
+	}
+}
@@ -4,8 +4,16 @@
 #[macro_use]
 extern crate pest_derive;
 
+pub mod compression;
+pub mod config;
 pub mod dimacs_cnf;
+mod ema;
 mod luby;
+pub mod proof;
+mod random;
 pub mod solver;
 pub mod stats;
+pub mod terminator;
 pub mod types;
+mod vmtf;
+mod vsids;
@@ -1,16 +1,206 @@
-use std::io::prelude::*;
-use std::{env, fs::File};
+use clap::{ArgAction, Parser, ValueEnum, ValueHint::FilePath};
+use meowc_sat::{
+	compression,
+	config::{Budget, Config, Heuristic, RestartPolicy, SearchMode},
+	dimacs_cnf::{read_dimacs, ParseMode},
+	proof::{Proof, ProofFormat},
+	types::SatResult,
+};
+use std::{
+	fs::File,
+	io::{BufWriter, Write},
+	path::PathBuf,
+	process,
+	time::{Duration, Instant},
+};
+
+// SAT competition exit codes
+const EXIT_SAT: i32 = 10;
+const EXIT_UNSAT: i32 = 20;
+const EXIT_UNKNOWN: i32 = 0;
+
+#[derive(Parser, Debug)]
+struct Args {
+	/// DIMACS CNF file to solve, which may be compressed
+	#[arg(value_hint = FilePath)]
+	dimacs_file: PathBuf,
+	/// File to write a proof of unsatisfiability to
+	#[arg(value_hint = FilePath)]
+	proof_file: Option<PathBuf>,
+
+	/// Write the proof in LRAT rather than DRAT
+	#[arg(long)]
+	lrat: bool,
+	/// Write the proof in the binary format
+	#[arg(long)]
+	binary_proof: bool,
+	/// File to write the model to as a v line
+	#[arg(short, long, value_hint = FilePath)]
+	model: Option<PathBuf>,
+	/// Infer the header from the clauses where it is wrong or missing
+	#[arg(long)]
+	lenient: bool,
+
+	/// Give up after this many seconds
+	#[arg(short, long, value_parser = parse_seconds)]
+	time_limit: Option<Duration>,
+	/// Give up after this many conflicts
+	#[arg(short, long)]
+	conflict_limit: Option<u64>,
+	/// Seed for the initial phases
+	#[arg(long)]
+	seed: Option<u64>,
+	/// Search mode to solve in, rather than alternating between both
+	#[arg(long, value_enum)]
+	mode: Option<ModeArg>,
+	/// Decision heuristic to use in both search modes
+	#[arg(long, value_enum)]
+	heuristic: Option<HeuristicArg>,
+	/// Restart policy to use in both search modes
+	#[arg(long, value_enum)]
+	restarts: Option<RestartArg>,
+	/// Dont remove subsumed clauses
+	#[arg(long)]
+	no_subsumption: bool,
+	/// Dont eliminate variables before solving
+	#[arg(long)]
+	no_elimination: bool,
+	/// Dont remove blocked clauses before solving
+	#[arg(long)]
+	no_blocked: bool,
+	/// Also remove covered clauses before solving
+	#[arg(long)]
+	covered: bool,
+	/// Dont probe for failed literals or substitute equivalent literals
+	#[arg(long)]
+	no_probing: bool,
+
+	/// Only print the result and model
+	#[arg(short, long)]
+	quiet: bool,
+	/// Print more details, can be given twice
+	#[arg(short, long, action = ArgAction::Count)]
+	verbose: u8,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ModeArg {
+	Focused,
+	Stable,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum HeuristicArg {
+	Vsids,
+	Vmtf,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum RestartArg {
+	Glucose,
+	Luby,
+	Geometric,
+}
+
+/// Only finite non-negative durations are accepted, as from_secs_f64 panics
+/// on anything else
+fn parse_seconds(arg: &str) -> Result<Duration, String> {
+	let seconds: f64 = arg.parse().map_err(|e| format!("{}", e))?;
+	Duration::try_from_secs_f64(seconds).map_err(|e| format!("{}", e))
+}
+
+fn config(args: &Args) -> Config {
+	let mut config = Config {
+		seed: args.seed,
+		subsumption: !args.no_subsumption,
+		elimination: !args.no_elimination,
+		blocked_elimination: !args.no_blocked,
+		covered_elimination: args.covered,
+		probing: !args.no_probing,
+		..Default::default()
+	};
+
+	if let Some(mode) = args.mode {
+		config.mode = match mode {
+			ModeArg::Focused => SearchMode::Focused,
+			ModeArg::Stable => SearchMode::Stable,
+		};
+		config.mode_switch_interval = None;
+	}
+
+	if let Some(heuristic) = args.heuristic {
+		let heuristic = match heuristic {
+			HeuristicArg::Vsids => Heuristic::Vsids,
+			HeuristicArg::Vmtf => Heuristic::Vmtf,
+		};
+		config.focused_heuristic = heuristic;
+		config.stable_heuristic = heuristic;
+	}
+
+	if let Some(restarts) = args.restarts {
+		let restarts = match restarts {
+			RestartArg::Glucose => RestartPolicy::Glucose,
+			RestartArg::Luby => RestartPolicy::Luby,
+			RestartArg::Geometric => RestartPolicy::Geometric,
+		};
+		config.focused_restarts = restarts;
+		config.stable_restarts = restarts;
+	}
+
+	config
+}
+
+fn fail(message: String) -> ! {
+	eprintln!("error: {}", message);
+	process::exit(1)
+}
 
 fn main() {
-	let args: Vec<_> = env::args().collect();
-	let mut file = File::open(&args[1]).unwrap();
-	let mut contents = String::new();
-	file.read_to_string(&mut contents).unwrap();
+	let args = Args::parse();
+	let verbosity = if args.quiet { 0 } else { args.verbose + 1 };
+
+	let file = compression::open(&args.dimacs_file).unwrap_or_else(|e| {
+		fail(format!("{}: {}", args.dimacs_file.display(), e))
+	});
+	let mode = if args.lenient {
+		ParseMode::Lenient
+	} else {
+		ParseMode::Strict
+	};
+	let mut solver = read_dimacs(file, mode).unwrap_or_else(|e| {
+		fail(format!("{}: {}", args.dimacs_file.display(), e))
+	});
+
+	solver.set_config(config(&args));
+	solver.set_budget(Budget {
+		conflicts: args.conflict_limit,
+		time: args.time_limit,
+		..Default::default()
+	});
+
+	let proof = args.proof_file.as_ref().map(|path| {
+		let format = match (args.lrat, args.binary_proof) {
+			(false, false) => ProofFormat::Drat,
+			(false, true) => ProofFormat::BinaryDrat,
+			(true, false) => ProofFormat::Lrat,
+			(true, true) => ProofFormat::BinaryLrat,
+		};
+		let file = File::create(path)
+			.unwrap_or_else(|e| fail(format!("{}: {}", path.display(), e)));
+		Proof::new(file, format)
+	});
+	if let Some(proof) = proof.as_ref() {
+		solver.set_proof(proof.clone());
+	}
 
-	let mut solver = meowc_sat::dimacs_cnf::parse_dimacs(&contents).unwrap();
+	// Interrupting stops the search, but still prints the stats and result
+	let terminator = solver.terminator();
+	ctrlc::set_handler(move || terminator.terminate())
+		.unwrap_or_else(|e| fail(format!("signal handler: {}", e)));
 
-	println!(
-		r"c  __  __ ______ ______          _______       _____      _______
+	if verbosity >= 1 {
+		println!(
+			r"c  __  __ ______ ______          _______       _____      _______
 c |  \/  |  ____/ __ \ \        / / ____|     / ____|  /\|__   __|
 c | \  / | |__ | |  | \ \  /\  / / |   ______| (___   /  \  | |
 c | |\/| |  __|| |  | |\ \/  \/ /| |  |______|\___ \ / /\ \ | |
@@ -18,17 +208,63 @@ c | |  | | |___| |__| | \  /\  / | |____      ____) / ____ \| |
 c |_|  |_|______\____/   \/  \/   \_____|    |_____/_/    \_\_|
 c nyaa~ :3
 c"
-	);
+		);
+	}
+	if verbosity >= 2 {
+		println!("c {:?}", solver.config());
+	}
 
-	println!("c ------------------------- Solving --------------------------");
-	solver.print_problem_stats();
+	if verbosity >= 1 {
+		println!(
+			"c ------------------------- Solving --------------------------"
+		);
+		solver.print_problem_stats();
+	}
+	let start = Instant::now();
 	let result = solver.solve();
-	println!("c -------------------------- Stats ---------------------------");
-	solver.print_stats();
-	println!("c -------------------------- Result --------------------------");
+	if verbosity >= 1 {
+		println!(
+			"c -------------------------- Stats ---------------------------"
+		);
+		solver.print_stats();
+		if verbosity >= 2 {
+			println!(
+				"c             time: {:9.3}s",
+				start.elapsed().as_secs_f64()
+			);
+		}
+		println!(
+			"c -------------------------- Result --------------------------"
+		);
+	}
+
 	println!("s {}", result);
-	if let meowc_sat::types::SatResult::Sat = result {
+	if let SatResult::Sat = result {
 		solver.print_assignment();
+		if let Some(path) = args.model.as_ref() {
+			let write_model = || {
+				let mut writer = BufWriter::new(File::create(path)?);
+				solver.write_assignment(&mut writer)?;
+				writer.flush()
+			};
+			write_model()
+				.unwrap_or_else(|e| fail(format!("{}: {}", path.display(), e)));
+		}
+	}
+	if let Some(proof) = proof {
+		proof
+			.flush()
+			.unwrap_or_else(|e| fail(format!("proof: {}", e)));
 	}
-	println!("c ------------------------------------------------------------");
+	if verbosity >= 1 {
+		println!(
+			"c ------------------------------------------------------------"
+		);
+	}
+
+	process::exit(match result {
+		SatResult::Sat => EXIT_SAT,
+		SatResult::Unsat => EXIT_UNSAT,
+		SatResult::Unknown => EXIT_UNKNOWN,
+	})
 }
@@ -0,0 +1,288 @@
+use crate::types::Lit;
+use std::{
+	io::{self, BufWriter, Write},
+	sync::{Arc, Mutex},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofFormat {
+	Drat,
+	BinaryDrat,
+	Lrat,
+	BinaryLrat,
+}
+
+impl ProofFormat {
+	/// Whether steps of the proof are numbered and list the clauses they are
+	/// derived from
+	#[inline]
+	pub fn is_lrat(&self) -> bool {
+		matches!(self, ProofFormat::Lrat | ProofFormat::BinaryLrat)
+	}
+}
+
+struct ProofWriter {
+	writer: BufWriter<Box<dyn Write + Send>>,
+	// The first write error, reported when the proof is flushed
+	error: Option<io::Error>,
+	// LRAT deletions are numbered by the most recent clause ID
+	last_id: u64,
+}
+
+/// Clausal proof of unsatisfiability written while solving. Clones share the
+/// same underlying writer, so a handle can be kept to flush the proof once the
+/// solver is done with it
+#[derive(Clone)]
+pub struct Proof {
+	writer: Arc<Mutex<ProofWriter>>,
+	format: ProofFormat,
+}
+
+impl Proof {
+	pub fn new<W: Write + Send + 'static>(
+		writer: W,
+		format: ProofFormat,
+	) -> Self {
+		Self {
+			writer: Arc::new(Mutex::new(ProofWriter {
+				writer: BufWriter::new(Box::new(writer)),
+				error: None,
+				last_id: 0,
+			})),
+			format,
+		}
+	}
+
+	#[inline]
+	pub fn format(&self) -> ProofFormat {
+		self.format
+	}
+
+	/// Adds a clause with the given ID. The hints are the IDs of the clauses
+	/// that become unit in turn and then conflict when the clause is negated,
+	/// and are only written for LRAT proofs
+	pub fn add(&self, id: u64, clause: &[Lit], hints: &[u64]) {
+		self.write_step(Step::Add(id, clause, hints));
+	}
+
+	pub fn delete(&self, id: u64, clause: &[Lit]) {
+		self.write_step(Step::Delete(id, clause));
+	}
+
+	pub fn flush(&self) -> io::Result<()> {
+		let mut writer = self.writer.lock().unwrap();
+		match writer.error.take() {
+			Some(error) => Err(error),
+			None => writer.writer.flush(),
+		}
+	}
+
+	fn write_step(&self, step: Step) {
+		let mut writer = self.writer.lock().unwrap();
+		if writer.error.is_some() {
+			return;
+		}
+
+		let id = match step {
+			Step::Add(id, ..) | Step::Delete(id, _) => id,
+		};
+		writer.last_id = writer.last_id.max(id);
+		let last_id = writer.last_id;
+
+		let result = match self.format {
+			ProofFormat::Drat => write_text(&mut writer.writer, step),
+			ProofFormat::BinaryDrat => write_binary(&mut writer.writer, step),
+			ProofFormat::Lrat => {
+				write_lrat_text(&mut writer.writer, step, last_id)
+			}
+			ProofFormat::BinaryLrat => {
+				write_lrat_binary(&mut writer.writer, step)
+			}
+		};
+
+		if let Err(error) = result {
+			writer.error = Some(error);
+		}
+	}
+}
+
+#[derive(Clone, Copy)]
+enum Step<'a> {
+	Add(u64, &'a [Lit], &'a [u64]),
+	Delete(u64, &'a [Lit]),
+}
+
+fn write_text<W: Write>(writer: &mut W, step: Step) -> io::Result<()> {
+	let clause = match step {
+		Step::Add(_, clause, _) => clause,
+		Step::Delete(_, clause) => {
+			write!(writer, "d ")?;
+			clause
+		}
+	};
+
+	for lit in clause.iter() {
+		write!(writer, "{} ", lit)?;
+	}
+
+	writeln!(writer, "0")
+}
+
+/// Each step is its kind as a byte, followed by each literal mapped to
+/// 2 * var + (lit < 0) as a little endian base 128 varint, then a zero byte
+fn write_binary<W: Write>(writer: &mut W, step: Step) -> io::Result<()> {
+	let (kind, clause) = match step {
+		Step::Add(_, clause, _) => (b'a', clause),
+		Step::Delete(_, clause) => (b'd', clause),
+	};
+
+	writer.write_all(&[kind])?;
+	for lit in clause.iter() {
+		write_varint(writer, encode_lit(*lit))?;
+	}
+	writer.write_all(&[0])
+}
+
+/// Additions are written as the ID, the literals and the hints, each list
+/// ending with 0. Deletions list the IDs of the deleted clauses after the
+/// last ID added
+fn write_lrat_text<W: Write>(
+	writer: &mut W,
+	step: Step,
+	last_id: u64,
+) -> io::Result<()> {
+	match step {
+		Step::Add(id, clause, hints) => {
+			write!(writer, "{} ", id)?;
+			for lit in clause.iter() {
+				write!(writer, "{} ", lit)?;
+			}
+			write!(writer, "0 ")?;
+			for hint in hints.iter() {
+				write!(writer, "{} ", hint)?;
+			}
+			writeln!(writer, "0")
+		}
+		Step::Delete(id, _) => writeln!(writer, "{} d {} 0", last_id, id),
+	}
+}
+
+/// Same layout as binary DRAT, with IDs encoded like positive literals
+fn write_lrat_binary<W: Write>(writer: &mut W, step: Step) -> io::Result<()> {
+	match step {
+		Step::Add(id, clause, hints) => {
+			writer.write_all(b"a")?;
+			write_varint(writer, 2 * id)?;
+			for lit in clause.iter() {
+				write_varint(writer, encode_lit(*lit))?;
+			}
+			writer.write_all(&[0])?;
+			for hint in hints.iter() {
+				write_varint(writer, 2 * hint)?;
+			}
+			writer.write_all(&[0])
+		}
+		Step::Delete(id, _) => {
+			writer.write_all(b"d")?;
+			write_varint(writer, 2 * id)?;
+			writer.write_all(&[0])
+		}
+	}
+}
+
+#[inline]
+fn encode_lit(lit: Lit) -> u64 {
+	2 * lit.var() as u64 + !lit.as_bool() as u64
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+	while value > 0x7f {
+		writer.write_all(&[(value & 0x7f) as u8 | 0x80])?;
+		value >>= 7;
+	}
+	writer.write_all(&[value as u8])
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		write_binary, write_lrat_binary, write_lrat_text, write_text,
+		write_varint, Step,
+	};
+	use crate::types::Lit;
+
+	fn lits(clause: &[i32]) -> Vec<Lit> {
+		clause.iter().map(|&lit| Lit::from(lit)).collect()
+	}
+
+	fn varint(value: u64) -> Vec<u8> {
+		let mut bytes = Vec::new();
+		write_varint(&mut bytes, value).unwrap();
+		bytes
+	}
+
+	#[test]
+	fn varints() {
+		assert_eq!(varint(0), [0x00]);
+		assert_eq!(varint(0x7f), [0x7f]);
+		assert_eq!(varint(0x80), [0x80, 0x01]);
+		assert_eq!(varint(0x3fff), [0xff, 0x7f]);
+		assert_eq!(varint(0x4000), [0x80, 0x80, 0x01]);
+		assert_eq!(varint(u64::MAX)[..9], [0xff; 9]);
+		assert_eq!(varint(u64::MAX)[9..], [0x01]);
+	}
+
+	#[test]
+	fn binary_drat() {
+		let mut bytes = Vec::new();
+		let clause = lits(&[1, -2, 63, -64]);
+		write_binary(&mut bytes, Step::Add(7, &clause, &[1])).unwrap();
+		write_binary(&mut bytes, Step::Delete(7, &lits(&[-1]))).unwrap();
+		write_binary(&mut bytes, Step::Add(8, &[], &[])).unwrap();
+
+		assert_eq!(
+			bytes,
+			[b'a', 2, 5, 126, 0x81, 0x01, 0, b'd', 3, 0, b'a', 0]
+		);
+	}
+
+	#[test]
+	fn text_drat() {
+		let mut bytes = Vec::new();
+		let clause = lits(&[1, -2]);
+		write_text(&mut bytes, Step::Add(7, &clause, &[1])).unwrap();
+		write_text(&mut bytes, Step::Delete(7, &clause)).unwrap();
+		write_text(&mut bytes, Step::Add(8, &[], &[])).unwrap();
+
+		assert_eq!(bytes, b"1 -2 0\nd 1 -2 0\n0\n");
+	}
+
+	fn lrat_binary(step: Step) -> Vec<u8> {
+		let mut bytes = Vec::new();
+		write_lrat_binary(&mut bytes, step).unwrap();
+		bytes
+	}
+
+	#[test]
+	fn binary_lrat() {
+		let clause = lits(&[1, -2]);
+		let add = Step::Add(5, &clause, &[1, 3]);
+		assert_eq!(lrat_binary(add), [b'a', 10, 2, 5, 0, 2, 6, 0]);
+		let delete = Step::Delete(70, &clause);
+		assert_eq!(lrat_binary(delete), [b'd', 0x8c, 0x01, 0]);
+		let empty = Step::Add(100, &[], &[64]);
+		assert_eq!(lrat_binary(empty), [b'a', 0xc8, 0x01, 0, 0x80, 0x01, 0]);
+	}
+
+	#[test]
+	fn text_lrat() {
+		let mut bytes = Vec::new();
+		let clause = lits(&[1, -2]);
+		let add = Step::Add(5, &clause, &[1, 3]);
+		write_lrat_text(&mut bytes, add, 5).unwrap();
+		write_lrat_text(&mut bytes, Step::Delete(3, &clause), 5).unwrap();
+		write_lrat_text(&mut bytes, Step::Add(6, &[], &[5]), 6).unwrap();
+
+		assert_eq!(bytes, b"5 1 -2 0 1 3 0\n5 d 3 0\n6 0 5 0\n");
+	}
+}
@@ -0,0 +1,26 @@
+/// SplitMix64 generator. Only used where the quality of the randomness isnt
+/// important, such as for initial phases
+/// https://prng.di.unimi.it/splitmix64.c
+#[derive(Clone)]
+pub struct Random {
+	state: u64,
+}
+
+impl Random {
+	pub fn new(seed: u64) -> Self {
+		Self { state: seed }
+	}
+
+	pub fn next(&mut self) -> u64 {
+		self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+		let mut z = self.state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+		z ^ (z >> 31)
+	}
+
+	#[inline]
+	pub fn next_bool(&mut self) -> bool {
+		self.next() & 1 == 1
+	}
+}
@@ -1,59 +1,220 @@
 use crate::{
-	luby::Luby,
+	config::{Budget, Config, Heuristic, SearchMode},
+	proof::Proof,
+	random::Random,
 	stats::Stats,
+	terminator::Terminator,
 	types::{Clause, ClauseId, DecisionLevel, Lit, SatResult, VarId},
+	vmtf::Vmtf,
+	vsids::Vsids,
 };
 
-const RESTART_SCALE: u64 = 512;
+use std::{
+	io::{self, Write},
+	time::Instant,
+};
+
+mod blocked;
+mod clause_db;
+mod eliminate;
+mod equivalence;
+mod lrat;
+mod minimize;
+mod probe;
+mod reconstruct;
+mod restart;
+mod subsume;
+#[cfg(test)]
+mod tests;
+use clause_db::{ClauseInfo, ReduceSchedule};
+use probe::ProbeSchedule;
+use restart::RestartSchedule;
+use subsume::SubsumeSchedule;
+
+/// Entry in the watch list of a literal. The blocker is some other literal of
+/// the clause, if it is satisfied the clause can be skipped without looking at
+/// the clause itself
+#[derive(Clone, Copy)]
+struct Watch {
+	clause: ClauseId,
+	blocker: Lit,
+}
 
 #[derive(Clone)]
 pub struct Solver {
+	// Deleted clauses leave an empty slot that is reused for later clauses
 	clauses: Vec<Clause>,
+	clause_info: Vec<ClauseInfo>,
+	learnts: Vec<ClauseId>,
+	free_clauses: Vec<ClauseId>,
+	clause_increment: f32,
+	lbd_stamps: Vec<u64>,
+	lbd_stamp: u64,
+	reduce_schedule: ReduceSchedule,
+	subsume_schedule: SubsumeSchedule,
+	probe_schedule: ProbeSchedule,
+
+	// Indexed by literal, holds the clauses that watch the negation of that
+	// literal, i.e. the clauses to visit when the literal becomes true. The
+	// watched literals of a clause are always its first two literals
+	watches: Vec<Vec<Watch>>,
+
+	// Assigned literals in assignment order. Literals before propagation_head
+	// have already had their watches visited. trail_lim holds the index in the
+	// trail at which each decision level starts
+	trail: Vec<Lit>,
+	trail_lim: Vec<usize>,
+	propagation_head: usize,
+	unsat: bool,
+	// Whether the assignment is a model, which it is from when solve returns
+	// Sat until the next call that changes it
+	has_model: bool,
+
+	// Assumptions are decided in order at the first decision levels. When
+	// they cause unsatisfiability the assumptions responsible are kept in
+	// final_conflict
+	assumptions: Vec<Lit>,
+	final_conflict: Vec<Lit>,
+
+	// The formula is simplified once before the first search. Clauses it
+	// removes are kept with their witness literals to reconstruct models, and
+	// eliminated variables stay assigned outside of the trail. Witnesses can
+	// be flipped by reconstruction, so neither they nor eliminated variables
+	// can be used again until the removed clauses are restored
+	preprocessed: bool,
+	eliminated: Vec<bool>,
+	witnesses: Vec<bool>,
+	reconstruction: Vec<(Lit, Clause)>,
 
-	decision_level: DecisionLevel,
 	num_vars: u32,
-	num_assigned: u32,
-	next_restart: u64,
+
+	// Search mode currently in use, which alternates between the two modes
+	// when mode switching is configured
+	mode: SearchMode,
+	restart_schedule: RestartSchedule,
 
 	assignments: Vec<Option<bool>>,
 	decision_levels: Vec<DecisionLevel>,
 	antecedents: Vec<ClauseId>,
+	seen: Vec<bool>,
+	analyzed: Vec<VarId>,
+	minimize_stack: Vec<VarId>,
+	minimize_to_clear: Vec<VarId>,
+	binary_minimized: Vec<ClauseId>,
 
 	phases: Vec<bool>,
-
-	frequencies: Vec<i32>,
-	frequencies_cache: Vec<i32>,
-
-	luby: Luby,
+	vsids: Vsids,
+	vmtf: Vmtf,
+
+	config: Config,
+
+	// The budget applies to each call to solve, so is measured from the stats
+	// and time when solving starts
+	budget: Budget,
+	budget_start: Stats,
+	deadline: Option<Instant>,
+	terminator: Terminator,
+
+	// IDs of clauses in the proof. Original clauses are numbered in the order
+	// they are added, including any that are dropped, so that they match the
+	// input formula. unit_ids holds the ID of the unit clause logged for each
+	// variable assigned at level 0, or 0 if there is none
+	proof: Option<Proof>,
+	next_proof_id: u64,
+	unit_ids: Vec<u64>,
 
 	stats: Stats,
 }
 
 impl Solver {
 	pub fn new(num_vars: u32) -> Self {
+		Self::with_config(num_vars, Default::default())
+	}
+
+	pub fn with_config(num_vars: u32, config: Config) -> Self {
 		// This accounts for index 0 being unused for simplicity
 		let num_vars = num_vars + 1;
 
-		Self {
+		let mut solver = Self {
 			clauses: vec![],
+			clause_info: vec![],
+			learnts: vec![],
+			free_clauses: vec![],
+			clause_increment: 1.0,
+			lbd_stamps: vec![0; num_vars as usize + 1],
+			lbd_stamp: 0,
+			reduce_schedule: Default::default(),
+			subsume_schedule: Default::default(),
+			probe_schedule: Default::default(),
+
+			watches: vec![vec![]; 2 * num_vars as usize],
+
+			trail: vec![],
+			trail_lim: vec![],
+			propagation_head: 0,
+			unsat: false,
+			has_model: false,
+
+			assumptions: vec![],
+			final_conflict: vec![],
+
+			preprocessed: false,
+			eliminated: vec![false; num_vars as usize],
+			witnesses: vec![false; num_vars as usize],
+			reconstruction: vec![],
 
-			decision_level: 0,
 			num_vars,
-			num_assigned: 0,
-			next_restart: RESTART_SCALE,
+
+			mode: config.mode,
+			restart_schedule: Default::default(),
 
 			assignments: vec![None; num_vars as usize],
 			decision_levels: vec![0; num_vars as usize],
 			antecedents: vec![-1; num_vars as usize],
+			seen: vec![false; num_vars as usize],
+			analyzed: vec![],
+			minimize_stack: vec![],
+			minimize_to_clear: vec![],
+			binary_minimized: vec![],
 
 			phases: vec![false; num_vars as usize],
-			frequencies: vec![0; num_vars as usize],
-			frequencies_cache: vec![0; num_vars as usize],
+			vsids: Vsids::new(num_vars as usize),
+			vmtf: Vmtf::new(num_vars as usize),
+
+			config,
+
+			budget: Default::default(),
+			budget_start: Default::default(),
+			deadline: None,
+			terminator: Default::default(),
 
-			luby: Default::default(),
+			proof: None,
+			next_proof_id: 1,
+			unit_ids: vec![0; num_vars as usize],
 
 			stats: Default::default(),
+		};
+
+		if let Some(seed) = solver.config.seed {
+			solver.randomize_phases(seed);
 		}
+		solver
+	}
+
+	/// Number of variables and the clauses kept as DIMACS literals, so that
+	/// tests can compare how solvers were built
+	#[cfg(test)]
+	pub(crate) fn formula(&self) -> (u32, Vec<Vec<i32>>) {
+		let clauses = self
+			.clauses
+			.iter()
+			.zip(self.clause_info.iter())
+			.filter(|(_, info)| !info.deleted)
+			.map(|(clause, _)| {
+				clause.iter().map(|&lit| i32::from(lit)).collect()
+			})
+			.collect();
+		(self.num_vars, clauses)
 	}
 
 	#[inline]
@@ -62,6 +223,70 @@ impl Solver {
 		println!("c   clauses: {:9}", self.clauses.len());
 	}
 
+	#[inline]
+	pub fn config(&self) -> &Config {
+		&self.config
+	}
+
+	/// Sets the configuration. A new seed rerandomizes the phases, so should be
+	/// set before solving
+	pub fn set_config(&mut self, config: Config) {
+		if config.seed != self.config.seed {
+			if let Some(seed) = config.seed {
+				self.randomize_phases(seed);
+			}
+		}
+		self.config = config;
+	}
+
+	fn randomize_phases(&mut self, seed: u64) {
+		let mut random = Random::new(seed);
+		for phase in self.phases.iter_mut() {
+			*phase = random.next_bool();
+		}
+	}
+
+	#[inline]
+	pub fn set_budget(&mut self, budget: Budget) {
+		self.budget = budget;
+	}
+
+	/// Handle to stop solving early. Clones of the solver share it
+	#[inline]
+	pub fn terminator(&self) -> Terminator {
+		self.terminator.clone()
+	}
+
+	/// Logs a proof of every clause learnt or deleted from here on, so should
+	/// be set before solving. Units and contradictions already found among the
+	/// clauses added so far are logged immediately
+	pub fn set_proof(&mut self, proof: Proof) {
+		self.proof = Some(proof);
+
+		if self.lrat() {
+			let level_end =
+				self.trail_lim.first().copied().unwrap_or(self.trail.len());
+			for i in 0..level_end {
+				let lit = self.trail[i];
+				if self.unit_ids[lit.var()] == 0 {
+					self.derive_unit(lit, self.antecedents[lit.var()]);
+				}
+			}
+		}
+
+		if self.unsat {
+			let conflict = (0..self.clauses.len())
+				.find(|&clause_id| {
+					!self.clause_info[clause_id].deleted
+						&& self.clauses[clause_id]
+							.iter()
+							.all(|&lit| self.value(lit) == Some(false))
+				})
+				.unwrap();
+			self.derive_empty_clause(conflict as ClauseId);
+		}
+	}
+
 	#[inline]
 	pub fn print_stats(&self) {
 		self.stats.print_summary();
@@ -69,125 +294,438 @@ impl Solver {
 
 	#[inline]
 	pub fn print_assignment(&self) {
-		print!("v ");
-		for (var, assignment) in self.assignments.iter().enumerate().skip(1) {
-			if let Some(pol) = assignment {
-				print!("{} ", Lit::from((var, *pol)));
-			}
+		self.write_assignment(io::stdout().lock()).unwrap();
+	}
+
+	/// Writes the assignment as a v line, as printed by print_assignment
+	pub fn write_assignment<W: Write>(&self, mut writer: W) -> io::Result<()> {
+		write!(writer, "v ")?;
+		for lit in self.assignment() {
+			write!(writer, "{} ", lit)?;
+		}
+		writeln!(writer, "0")
+	}
+
+	/// Literals that are currently true, in order of their variables
+	pub fn assignment(&self) -> impl Iterator<Item = Lit> + '_ {
+		self.assignments.iter().enumerate().skip(1).filter_map(
+			|(var, assignment)| assignment.map(|pol| Lit::from((var, pol))),
+		)
+	}
+
+	/// The model found by the last call to solve, if it returned Sat and the
+	/// formula hasnt been changed since
+	pub fn model(&self) -> Option<Vec<Lit>> {
+		self.has_model.then(|| self.assignment().collect())
+	}
+
+	#[inline]
+	fn heuristic(&self) -> Heuristic {
+		match self.mode {
+			SearchMode::Focused => self.config.focused_heuristic,
+			SearchMode::Stable => self.config.stable_heuristic,
 		}
-		println!("0");
 	}
 
 	#[inline]
-	fn all_assigned(&self) -> bool {
-		self.num_assigned == self.num_vars
+	fn decision_level(&self) -> DecisionLevel {
+		self.trail_lim.len() as DecisionLevel
+	}
+
+	#[inline]
+	fn new_decision_level(&mut self) {
+		self.trail_lim.push(self.trail.len());
+	}
+
+	/// Value of lit under the current assignment, which is a model whenever
+	/// model returns one
+	#[inline]
+	pub fn value(&self, lit: Lit) -> Option<bool> {
+		self.assignments[lit.var()].map(|pol| pol == lit.as_bool())
 	}
 
 	pub fn solve(&mut self) -> SatResult {
-		self.decision_level = 0;
+		self.solve_with_assumptions(&[])
+	}
+
+	/// Solves the formula under the given assumptions, which only hold for
+	/// this call. Learnt clauses are kept between calls, and if the result is
+	/// Unsat because of the assumptions the failed ones are available through
+	/// final_conflict and failed. Returns Unknown once the budget runs out or
+	/// the terminator is set
+	pub fn solve_with_assumptions(&mut self, assumptions: &[Lit]) -> SatResult {
+		self.backtrack(0);
+		self.has_model = false;
+		self.final_conflict.clear();
+		self.assumptions.clear();
+		self.assumptions.extend_from_slice(assumptions);
+
+		if assumptions.iter().any(|lit| self.is_removed(lit.var())) {
+			self.restore_eliminated();
+		}
 
-		if let Err(_) = self.unit_propagate() {
+		if self.unsat {
+			return SatResult::Unsat;
+		}
+		self.start_budget();
+		self.reset_search_mode();
+		if let Err(conflict_clause) = self.unit_propagate() {
+			self.set_unsat(conflict_clause);
 			return SatResult::Unsat;
 		}
 
-		while !self.all_assigned() {
-			self.decision_level += 1;
+		if !self.preprocessed {
+			self.preprocessed = true;
+			self.preprocess();
+			if self.unsat {
+				return SatResult::Unsat;
+			}
+		}
+
+		loop {
+			// Checked before picking a decision, as the heuristics remove the
+			// variable they pick
+			if self.budget_exhausted() {
+				return SatResult::Unknown;
+			}
+
+			let level = self.decision_level() as usize;
+			let decision = match self.assumptions.get(level) {
+				Some(&assumption) => match self.value(assumption) {
+					// Open an empty decision level so that each assumption
+					// stays at the level matching its index
+					Some(true) => {
+						self.new_decision_level();
+						continue;
+					}
+					Some(false) => {
+						self.analyze_final(assumption);
+						return SatResult::Unsat;
+					}
+					None => assumption,
+				},
+				None => match self.choose_assignment() {
+					Some(decision) => decision,
+					None => {
+						self.extend_model();
+						self.has_model = true;
+						return SatResult::Sat;
+					}
+				},
+			};
+
+			self.stats.decisions += 1;
+			self.new_decision_level();
 
-			self.assign(self.choose_assignment(), -1);
+			self.assign(decision, -1);
 
 			while let Err(conflict_clause) = self.unit_propagate() {
-				if self.decision_level == 0 {
+				if self.decision_level() == 0 {
+					self.set_unsat(conflict_clause);
 					return SatResult::Unsat;
 				}
 
 				self.conflict_analysis(conflict_clause);
 				self.stats.conflicts += 1;
 
-				if self.should_restart() {
+				if self.should_switch_mode() {
+					self.switch_mode();
+				} else if self.should_restart() {
 					self.stats.restarts += 1;
 					self.backtrack(0);
+
+					self.inprocess();
+					if self.unsat {
+						return SatResult::Unsat;
+					}
+				}
+
+				if self.should_reduce() {
+					self.reduce_db();
+				}
+
+				if self.budget_exhausted() {
+					return SatResult::Unknown;
 				}
 			}
 		}
+	}
+
+	/// Simplifies the formula at level 0 before the first search
+	fn preprocess(&mut self) {
+		// Removed clauses can only be restored in a proof as RAT steps, so
+		// nothing is removed when writing LRAT
+		let removable = !self.lrat();
 
-		SatResult::Sat
+		if self.config.probing {
+			self.probe();
+		}
+		if self.config.probing && removable && !self.unsat {
+			self.substitute_equivalences();
+		}
+		if self.config.subsumption && !self.unsat {
+			self.subsume(false);
+		}
+		if self.config.blocked_elimination && removable && !self.unsat {
+			self.eliminate_blocked(self.config.covered_elimination);
+		}
+		if self.config.elimination && removable && !self.unsat {
+			self.eliminate_variables();
+		}
 	}
 
-	pub fn add_clause(&mut self, clause: &Clause) {
-		for lit in clause.iter() {
-			let var = lit.var();
+	/// Simplifies the formula at level 0 after a restart, when the schedules
+	/// of the simplifications are due
+	fn inprocess(&mut self) {
+		// The restart can leave a learnt unit to propagate
+		if let Err(conflict) = self.unit_propagate() {
+			self.set_unsat(conflict);
+			return;
+		}
 
-			if self.frequencies[var] != -1 {
-				self.frequencies[var] += 1;
+		if self.config.probing && self.should_probe() {
+			self.probe();
+			if !self.lrat() && !self.unsat {
+				self.substitute_equivalences();
 			}
-			self.frequencies_cache[var] += 1;
 		}
+		if self.config.subsumption && !self.unsat && self.should_subsume() {
+			self.subsume(true);
+		}
+	}
+
+	fn start_budget(&mut self) {
+		self.budget_start = self.stats;
+		self.deadline = self.budget.time.map(|time| Instant::now() + time);
+	}
+
+	/// Checked before each decision and after each conflict, so limits can be
+	/// overrun by up to one round of propagation
+	fn budget_exhausted(&self) -> bool {
+		let (stats, start) = (&self.stats, &self.budget_start);
+		let limits = [
+			(self.budget.conflicts, stats.conflicts - start.conflicts),
+			(
+				self.budget.propagations,
+				stats.propagations - start.propagations,
+			),
+			(self.budget.decisions, stats.decisions - start.decisions),
+		];
+
+		let exceeded =
+			|&(limit, used)| matches!(limit, Some(limit) if used >= limit);
+		let timed_out = matches!(
+			self.deadline,
+			Some(deadline) if Instant::now() >= deadline
+		);
+
+		self.terminator.is_terminated()
+			|| limits.iter().any(exceeded)
+			|| timed_out
+	}
+
+	/// Assumptions of the last call to solve_with_assumptions that were used
+	/// to show the formula unsatisfiable. Empty if the formula is
+	/// unsatisfiable without any assumptions
+	#[inline]
+	pub fn final_conflict(&self) -> &[Lit] {
+		&self.final_conflict
+	}
+
+	/// Whether the assumption lit is part of the final conflict
+	#[inline]
+	pub fn failed(&self, lit: Lit) -> bool {
+		self.final_conflict.contains(&lit)
+	}
+
+	/// Adds a clause to the formula. This can be done between calls to solve,
+	/// in which case the assignment from the last call is discarded
+	pub fn add_clause(&mut self, clause: &Clause) {
+		if clause.iter().any(|lit| self.is_removed(lit.var())) {
+			self.restore_eliminated();
+		}
+
+		self.backtrack(0);
+		self.has_model = false;
+		let proof_id = self.new_proof_id();
+
+		let mut clause = clause.to_vec();
+		clause.sort();
+		clause.dedup();
+
+		// Tautologies are always satisfied. Sorting places x and -x next to
+		// each other
+		if clause.windows(2).any(|lits| lits[0].var() == lits[1].var()) {
+			return;
+		}
+
+		let clause_id =
+			self.store_clause(clause, ClauseInfo::original(proof_id));
+		self.attach_clause(clause_id);
+	}
 
-		self.clauses.push(clause.to_vec());
+	/// Watches a clause stored at level 0, assigning it if it is unit. Units
+	/// are left for the next propagation
+	fn attach_clause(&mut self, clause_id: ClauseId) {
+		// Literals may already be assigned at level 0 by earlier clauses.
+		// Watch literals that are not false where possible, which makes it
+		// unit if only one is left
+		let mut clause = std::mem::take(&mut self.clauses[clause_id as usize]);
+		clause.sort_by_key(|&lit| match self.value(lit) {
+			Some(true) => 0,
+			None => 1,
+			Some(false) => 2,
+		});
+		self.clauses[clause_id as usize] = clause;
+
+		let len = self.clauses[clause_id as usize].len();
+
+		if len == 0 {
+			self.set_unsat(clause_id);
+			return;
+		}
+
+		if len > 1 {
+			self.watch_clause(clause_id);
+		}
+
+		let lit = self.clauses[clause_id as usize][0];
+		let unit = len == 1
+			|| self.value(self.clauses[clause_id as usize][1]) == Some(false);
+
+		match self.value(lit) {
+			Some(false) => self.set_unsat(clause_id),
+			None if unit => self.assign(lit, clause_id),
+			_ => (),
+		}
 	}
 
-	fn should_restart(&mut self) -> bool {
-		let should_restart = self.stats.conflicts >= self.next_restart;
-		if should_restart {
-			self.next_restart =
-				self.stats.conflicts + self.luby.next() * RESTART_SCALE;
+	/// Marks the formula as unsatisfiable regardless of assumptions because
+	/// the conflict clause is false at level 0, which completes the proof with
+	/// the empty clause
+	fn set_unsat(&mut self, conflict: ClauseId) {
+		if !self.unsat {
+			self.unsat = true;
+			self.derive_empty_clause(conflict);
 		}
+	}
 
-		should_restart
+	#[inline]
+	fn watch_clause(&mut self, clause_id: ClauseId) {
+		let clause = &self.clauses[clause_id as usize];
+		self.watches[(!clause[0]).index()].push(Watch {
+			clause: clause_id,
+			blocker: clause[1],
+		});
+		self.watches[(!clause[1]).index()].push(Watch {
+			clause: clause_id,
+			blocker: clause[0],
+		});
 	}
 
 	fn unit_propagate(&mut self) -> Result<(), ClauseId> {
-		'outer_loop: loop {
-			'clause_loop: for (clause_id, clause) in
-				self.clauses.iter().enumerate()
-			{
-				let mut unassigned_lit = None;
-
-				for lit in clause.iter() {
-					match self.assignments[lit.var()] {
-						// If literal is sat clause is sat so go to next clause
-						Some(assignment) if assignment == lit.as_bool() => {
-							continue 'clause_loop
-						}
-
-						// If more than one unassigned var we cant do anything
-						// so go to next clause, else save unassigned var
-						None => match unassigned_lit {
-							Some(_) => continue 'clause_loop,
-							None => unassigned_lit = Some(*lit),
-						},
-
-						_ => (),
-					}
-				}
+		while self.propagation_head < self.trail.len() {
+			let lit = self.trail[self.propagation_head];
+			self.propagation_head += 1;
+
+			self.propagate_lit(lit)?;
+		}
+
+		Ok(())
+	}
 
-				// All clauses still considered here have either 0 or 1
-				// unassigned vars and the rest unsatisfied by the assignment
+	/// Visits the clauses watching !lit after lit has been made true, moving
+	/// watches to other unassigned literals where possible and assigning
+	/// clauses that have become unit
+	fn propagate_lit(&mut self, lit: Lit) -> Result<(), ClauseId> {
+		let false_lit = !lit;
+
+		// Taken so the clauses can be modified while the list is walked, the
+		// kept watches are compacted into the front of the list
+		let mut watches = std::mem::take(&mut self.watches[lit.index()]);
+		let mut kept = 0;
+		let mut result = Ok(());
+
+		let mut i = 0;
+		'watch_loop: while i < watches.len() {
+			let watch = watches[i];
+			i += 1;
+
+			// Satisfied blocker means the clause is satisfied
+			if self.value(watch.blocker) == Some(true) {
+				watches[kept] = watch;
+				kept += 1;
+				continue;
+			}
+
+			// Make sure the false literal is the second watch
+			let clause = &mut self.clauses[watch.clause as usize];
+			if clause[0] == false_lit {
+				clause.swap(0, 1);
+			}
 
-				// Unsat
-				if unassigned_lit.is_none() {
-					return Err(clause_id as ClauseId);
+			let first = clause[0];
+			let watch = Watch {
+				clause: watch.clause,
+				blocker: first,
+			};
+
+			let first_value =
+				self.assignments[first.var()].map(|pol| pol == first.as_bool());
+			if first_value == Some(true) {
+				watches[kept] = watch;
+				kept += 1;
+				continue;
+			}
+
+			// Look for a new literal to watch
+			for k in 2..clause.len() {
+				let candidate = clause[k];
+				let candidate_value = self.assignments[candidate.var()]
+					.map(|pol| pol == candidate.as_bool());
+
+				if candidate_value != Some(false) {
+					clause.swap(1, k);
+					self.watches[(!candidate).index()].push(watch);
+					continue 'watch_loop;
 				}
+			}
+
+			// No new watch so the clause is either unit or conflicting
+			watches[kept] = watch;
+			kept += 1;
 
-				// Unit clause
-				self.assign(unassigned_lit.unwrap(), clause_id as ClauseId);
+			if first_value.is_none() {
+				self.assign(first, watch.clause);
 				self.stats.propagations += 1;
-				continue 'outer_loop;
+			} else {
+				// Keep the watches that havent been visited yet
+				while i < watches.len() {
+					watches[kept] = watches[i];
+					kept += 1;
+					i += 1;
+				}
+				result = Err(watch.clause);
 			}
-
-			// No unit clauses found - unit clauses cause the outer_loop to be
-			// continued early
-			return Ok(());
 		}
+
+		watches.truncate(kept);
+		self.watches[lit.index()] = watches;
+
+		result
 	}
 
 	fn assign(&mut self, lit: Lit, antecedent: ClauseId) {
+		self.trail.push(lit);
+
 		let var = lit.var();
 		self.assignments[var] = Some(lit.as_bool());
-		self.decision_levels[var] = self.decision_level;
+		self.decision_levels[var] = self.decision_level();
 		self.antecedents[var] = antecedent;
-		self.frequencies[var] = -1;
-		self.num_assigned += 1;
+
+		if self.decision_level() == 0 && self.lrat() {
+			self.derive_unit(lit, antecedent);
+		}
 	}
 
 	fn unassign(&mut self, var: VarId) {
@@ -197,109 +735,215 @@ impl Solver {
 
 		self.assignments[var] = None;
 		self.antecedents[var] = -1;
-		self.frequencies[var] = self.frequencies_cache[var];
-		self.num_assigned -= 1;
+		self.vsids.insert(var);
+		self.vmtf.on_unassign(var);
 	}
 
 	fn conflict_analysis(&mut self, conflict_id: ClauseId) {
-		let learnt_clause = self.derive_1uip_clause(conflict_id);
+		let mut learnt_clause = self.derive_1uip_clause(conflict_id);
+		self.minimize_clause(&mut learnt_clause);
+		self.stats.learnt_literals += learnt_clause.len() as u64;
+
+		let hints = if self.lrat() {
+			let binaries = std::mem::take(&mut self.binary_minimized);
+			let hints =
+				self.chain_hints(&learnt_clause, &binaries, conflict_id);
+			self.binary_minimized = binaries;
+			hints
+		} else {
+			vec![]
+		};
+		let proof_id = self.new_proof_id();
+		if let Some(proof) = self.proof.as_ref() {
+			proof.add(proof_id, &learnt_clause, &hints);
+		}
 
-		self.add_clause(&learnt_clause);
+		self.bump_analyzed();
+		self.decay_clause_activity();
+
+		// The UIP is first, put the literal with the greatest decision level
+		// below the conflict level second. After backtracking to that level
+		// the first literal is unit and the two watched literals are the last
+		// to be unassigned
+		for i in 2..learnt_clause.len() {
+			let level = self.decision_levels[learnt_clause[i].var()];
+			if level > self.decision_levels[learnt_clause[1].var()] {
+				learnt_clause.swap(1, i);
+			}
+		}
 
-		// Find greatest decision level below conflict decision level that
-		// assigns to the learnt clause for backgracking
+		let lbd = self.compute_lbd(&learnt_clause);
+		self.update_restart_averages(lbd);
 		self.backtrack(
 			learnt_clause
-				.iter()
-				.map(|lit| self.decision_levels[lit.var() as usize])
-				.filter(|&level| level < self.decision_level)
-				.max()
+				.get(1)
+				.map(|lit| self.decision_levels[lit.var()])
 				.unwrap_or(0),
 		);
+
+		let uip = learnt_clause[0];
+		let len = learnt_clause.len();
+		let clause_id =
+			self.store_clause(learnt_clause, ClauseInfo::learnt(lbd, proof_id));
+		if len > 1 {
+			self.watch_clause(clause_id);
+		}
+		self.assign(uip, clause_id);
 	}
 
-	/// Backtracks to self.decision_level
+	/// Backtracks to backtrack_level, unassigning everything assigned at a
+	/// greater decision level
 	fn backtrack(&mut self, backtrack_level: DecisionLevel) {
-		// TODO: Can this be iterators?
-		for var in 0..self.decision_levels.len() {
-			if self.decision_levels[var] > backtrack_level {
-				self.unassign(var);
-			}
+		if self.decision_level() <= backtrack_level {
+			return;
+		}
+
+		let level_start = self.trail_lim[backtrack_level as usize];
+		while self.trail.len() > level_start {
+			let lit = unsafe { self.trail.pop().unwrap_unchecked() };
+			self.unassign(lit.var());
 		}
 
-		self.decision_level = backtrack_level;
+		self.trail_lim.truncate(backtrack_level as usize);
+		self.propagation_head = level_start;
 	}
 
+	/// Derives the first UIP clause by resolving the conflict clause with the
+	/// antecedents of literals at the conflict level in reverse trail order,
+	/// until only one literal at the conflict level remains. The UIP is the
+	/// first literal of the returned clause, and the variables of the other
+	/// literals are left marked as seen
 	fn derive_1uip_clause(&mut self, conflict_id: ClauseId) -> Clause {
-		let mut learnt_clause = self.clauses[conflict_id as usize].clone();
-
-		loop {
-			let mut conflict_level_lits: u32 = 0;
-			let mut resolvent_lit = None;
+		let mut learnt_clause = vec![];
+		let mut conflict_level_lits = 0;
+		let mut clause_id = conflict_id;
+		let mut resolved_var: VarId = 0;
+		let mut trail_index = self.trail.len();
+
+		let uip = loop {
+			if self.clause_info[clause_id as usize].learnt {
+				self.bump_clause(clause_id);
+			}
 
-			for lit in learnt_clause.iter() {
+			for &lit in self.clauses[clause_id as usize].iter() {
 				let var = lit.var();
 
-				// Only consider literals assigned at the conflict decicision
-				// level
-				if self.decision_levels[var] != self.decision_level {
+				// Literals at level 0 are false in every assignment so can be
+				// dropped
+				if var == resolved_var
+					|| self.seen[var]
+					|| self.decision_levels[var] == 0
+				{
 					continue;
 				}
 
-				conflict_level_lits += 1;
+				self.seen[var] = true;
+				self.analyzed.push(var);
 
-				// If literal at this level has antecedent, save for resolving
-				if self.antecedents[var] != -1 {
-					resolvent_lit = Some(*lit);
+				if self.decision_levels[var] == self.decision_level() {
+					conflict_level_lits += 1;
+				} else {
+					learnt_clause.push(lit);
 				}
 			}
 
+			// Walk back to the most recently assigned literal in the clause
+			loop {
+				trail_index -= 1;
+				if self.seen[self.trail[trail_index].var()] {
+					break;
+				}
+			}
+
+			let resolvent_lit = self.trail[trail_index];
+			self.seen[resolvent_lit.var()] = false;
+
 			// One lit at the conflict level means that is a UIP
-			if conflict_level_lits == 1 {
-				break;
+			conflict_level_lits -= 1;
+			if conflict_level_lits == 0 {
+				break !resolvent_lit;
 			}
 
-			debug_assert!(resolvent_lit.is_some());
-			learnt_clause = self.resolve(&learnt_clause, unsafe {
-				resolvent_lit.unwrap_unchecked()
-			});
-		}
+			resolved_var = resolvent_lit.var();
+			clause_id = self.antecedents[resolved_var];
+		};
 
+		learnt_clause.insert(0, uip);
 		learnt_clause
 	}
 
-	fn resolve(&self, clause: &Clause, resolvent: Lit) -> Clause {
-		let var = resolvent.var();
-		let antecedent = &self.clauses[self.antecedents[var] as usize];
+	/// Finds the assumptions responsible for the failed assumption being
+	/// false, by walking back through its implication graph to the decisions
+	/// it depends on. Every decision made so far is an assumption
+	fn analyze_final(&mut self, failed: Lit) {
+		self.final_conflict.push(failed);
 
-		// TODO: This isnt modifying in place, is this an issue
-		// Join clauses and remove resolvent
-		let mut new_clause: Clause = clause
-			.iter()
-			.chain(antecedent.iter())
-			.filter(|lit| lit.var() != var)
-			.cloned()
-			.collect();
+		if self.decision_levels[failed.var()] == 0 {
+			return;
+		}
+
+		self.seen[failed.var()] = true;
 
-		// Remove duplicates
-		new_clause.sort();
-		new_clause.dedup();
+		for i in (self.trail_lim[0]..self.trail.len()).rev() {
+			let lit = self.trail[i];
+			let var = lit.var();
+
+			if !self.seen[var] {
+				continue;
+			}
+
+			let antecedent = self.antecedents[var];
+			if antecedent == -1 {
+				self.final_conflict.push(lit);
+			} else {
+				for k in 0..self.clauses[antecedent as usize].len() {
+					let other = self.clauses[antecedent as usize][k].var();
+					if self.decision_levels[other] > 0 {
+						self.seen[other] = true;
+					}
+				}
+			}
 
-		new_clause
+			self.seen[var] = false;
+		}
 	}
 
-	fn choose_assignment(&self) -> Lit {
-		// TODO: Make this a better scheme, atm it just picks the max freq
-		// unassigned var
+	/// Bumps the variables seen in the last conflict analysis in the active
+	/// decision heuristic
+	fn bump_analyzed(&mut self) {
+		match self.heuristic() {
+			Heuristic::Vsids => {
+				for &var in self.analyzed.iter() {
+					self.vsids.bump(var);
+				}
+				self.vsids.decay(self.config.vsids_decay);
+			}
+			Heuristic::Vmtf => {
+				// Bumping in stamp order keeps the relative order of the
+				// bumped variables in the queue
+				let vmtf = &mut self.vmtf;
+				self.analyzed.sort_unstable_by_key(|&var| vmtf.stamp(var));
+				for &var in self.analyzed.iter() {
+					vmtf.bump(var);
+				}
+			}
+		}
 
-		let var: VarId = self
-			.frequencies
-			.iter()
-			.enumerate()
-			.max_by_key(|&(_, item)| item)
-			.unwrap()
-			.0;
+		self.analyzed.clear();
+	}
+
+	fn choose_assignment(&mut self) -> Option<Lit> {
+		let var = match self.heuristic() {
+			Heuristic::Vsids => loop {
+				// Assigned variables are only removed from the heap lazily
+				let var = self.vsids.pop_max()?;
+				if self.assignments[var].is_none() {
+					break var;
+				}
+			},
+			Heuristic::Vmtf => self.vmtf.next_unassigned(&self.assignments)?,
+		};
 
-		Lit::from((var, self.phases[var]))
+		Some(Lit::from((var, self.phases[var])))
 	}
 }
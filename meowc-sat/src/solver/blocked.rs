@@ -0,0 +1,155 @@
+use super::Solver;
+use crate::types::{Clause, ClauseId, Lit};
+
+// Pivots with more clauses to resolve with than this are skipped, and clauses
+// extended by covered literals beyond this length are given up on
+const OCCURRENCE_LIMIT: usize = 64;
+const COVERED_LIMIT: usize = 64;
+
+impl Solver {
+	/// Removes original clauses that are blocked, meaning they contain a
+	/// literal l such that every resolvent on l is a tautology. With covered,
+	/// clauses are first extended by covered literal addition, which adds the
+	/// literals that every non-tautological resolvent on some l would contain,
+	/// so that clauses that are blocked once extended are removed too. The
+	/// removals are logged in the proof as deletions, which keeps DRAT proofs
+	/// valid as the remaining formula only gets weaker. Restoring removed
+	/// clauses takes RAT steps, so this isnt called when writing LRAT. Must be
+	/// called at level 0 after propagating
+	pub(super) fn eliminate_blocked(&mut self, covered: bool) {
+		let occurrences = self.occurrence_lists(false);
+
+		let mut frozen = vec![false; self.num_vars as usize];
+		for &lit in self.assumptions.iter() {
+			frozen[lit.var()] = true;
+		}
+
+		let mut marks = vec![false; self.watches.len()];
+		let mut deleted = vec![];
+
+		for clause_id in 0..self.clauses.len() {
+			let info = &self.clause_info[clause_id];
+			if info.learnt || info.deleted {
+				continue;
+			}
+
+			let clause_id = clause_id as ClauseId;
+			let steps = match self.covered_steps(
+				clause_id,
+				covered,
+				&occurrences,
+				&frozen,
+				&mut marks,
+			) {
+				Some(steps) => steps,
+				None => continue,
+			};
+
+			if steps.len() > 1 {
+				self.stats.covered_clauses += 1;
+			} else {
+				self.stats.blocked_clauses += 1;
+			}
+
+			for (witness, clause) in steps {
+				self.push_reconstruction(witness, clause);
+			}
+			self.delete_clause(clause_id);
+			deleted.push(clause_id);
+		}
+
+		self.collect_deleted(deleted);
+	}
+
+	/// Reconstruction entries that remove the clause if it is blocked, once
+	/// extended by covered literals if covered is set. Each entry is a pivot
+	/// with the clause as it was before extending it on that pivot, so that
+	/// flipping the pivot repairs a model of the extended clause, ending in
+	/// the blocking literal with the fully extended clause
+	fn covered_steps(
+		&self,
+		clause_id: ClauseId,
+		covered: bool,
+		occurrences: &[Vec<ClauseId>],
+		frozen: &[bool],
+		marks: &mut [bool],
+	) -> Option<Vec<(Lit, Clause)>> {
+		let mut clause = self.clauses[clause_id as usize].clone();
+		for &lit in clause.iter() {
+			marks[lit.index()] = true;
+		}
+
+		let mut steps = vec![];
+		let mut blocking = None;
+
+		// Literals added by covering are tried as pivots too
+		let mut i = 0;
+		while i < clause.len() && clause.len() <= COVERED_LIMIT {
+			let pivot = clause[i];
+			i += 1;
+
+			// Pivots are flipped when reconstructing models, so cant be
+			// assumed or fixed at level 0
+			let others = &occurrences[(!pivot).index()];
+			if frozen[pivot.var()]
+				|| self.value(pivot).is_some()
+				|| others.len() > OCCURRENCE_LIMIT
+			{
+				continue;
+			}
+
+			// Literals in every non-tautological resolvent on the pivot
+			let mut common: Option<Clause> = None;
+			for &other_id in others.iter() {
+				let other = &self.clauses[other_id as usize];
+				if self.clause_info[other_id as usize].deleted
+					|| other
+						.iter()
+						.any(|&lit| lit != !pivot && marks[(!lit).index()])
+				{
+					continue;
+				}
+
+				let lits = other
+					.iter()
+					.copied()
+					.filter(|&lit| lit != !pivot && !marks[lit.index()]);
+				match common.as_mut() {
+					Some(common) => {
+						let lits: Clause = lits.collect();
+						common.retain(|lit| lits.contains(lit));
+					}
+					None => common = Some(lits.collect()),
+				}
+
+				if !covered && common.is_some() {
+					break;
+				}
+			}
+
+			match common {
+				None => {
+					blocking = Some(pivot);
+					break;
+				}
+				Some(common) if covered && !common.is_empty() => {
+					steps.push((pivot, clause.clone()));
+					for &lit in common.iter() {
+						marks[lit.index()] = true;
+					}
+					clause.extend(common);
+				}
+				Some(_) => (),
+			}
+		}
+
+		for &lit in clause.iter() {
+			marks[lit.index()] = false;
+		}
+
+		blocking.map(|blocking| {
+			steps.push((blocking, clause));
+			steps
+		})
+	}
+}
@@ -0,0 +1,270 @@
+use super::Solver;
+use crate::types::{Clause, ClauseId, Lit};
+
+// Learnt clauses with an LBD up to CORE_LBD are kept forever, up to TIER2_LBD
+// are kept while they keep being used in conflicts, and the rest are local
+// clauses of which the least active half is dropped at each reduction
+const CORE_LBD: u32 = 2;
+const TIER2_LBD: u32 = 6;
+
+const REDUCE_FIRST: u64 = 2000;
+const REDUCE_INCREMENT: u64 = 300;
+
+const CLAUSE_DECAY: f32 = 0.999;
+const CLAUSE_RESCALE_LIMIT: f32 = 1e20;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub(super) enum Tier {
+	Core,
+	Tier2,
+	Local,
+}
+
+impl Tier {
+	fn from_lbd(lbd: u32) -> Self {
+		if lbd <= CORE_LBD {
+			Tier::Core
+		} else if lbd <= TIER2_LBD {
+			Tier::Tier2
+		} else {
+			Tier::Local
+		}
+	}
+}
+
+#[derive(Clone)]
+pub(super) struct ClauseInfo {
+	pub learnt: bool,
+	pub deleted: bool,
+	pub proof_id: u64,
+
+	// Only meaningful for learnt clauses. used is set whenever the clause takes
+	// part in conflict analysis and cleared at each reduction
+	pub lbd: u32,
+	pub tier: Tier,
+	pub activity: f32,
+	pub used: bool,
+}
+
+impl ClauseInfo {
+	pub fn original(proof_id: u64) -> Self {
+		Self {
+			learnt: false,
+			deleted: false,
+			proof_id,
+			lbd: 0,
+			tier: Tier::Core,
+			activity: 0.0,
+			used: false,
+		}
+	}
+
+	pub fn learnt(lbd: u32, proof_id: u64) -> Self {
+		Self {
+			learnt: true,
+			deleted: false,
+			proof_id,
+			lbd,
+			tier: Tier::from_lbd(lbd),
+			activity: 0.0,
+			used: false,
+		}
+	}
+}
+
+#[derive(Clone)]
+pub(super) struct ReduceSchedule {
+	next: u64,
+	increment: u64,
+}
+
+impl Default for ReduceSchedule {
+	fn default() -> Self {
+		Self {
+			next: REDUCE_FIRST,
+			increment: REDUCE_FIRST,
+		}
+	}
+}
+
+impl Solver {
+	/// Stores a clause, reusing the slot of a deleted clause if there is one
+	pub(super) fn store_clause(
+		&mut self,
+		clause: Clause,
+		info: ClauseInfo,
+	) -> ClauseId {
+		let learnt = info.learnt;
+
+		let clause_id = match self.free_clauses.pop() {
+			Some(clause_id) => {
+				self.clauses[clause_id as usize] = clause;
+				self.clause_info[clause_id as usize] = info;
+				clause_id
+			}
+			None => {
+				self.clauses.push(clause);
+				self.clause_info.push(info);
+				(self.clauses.len() - 1) as ClauseId
+			}
+		};
+
+		if learnt {
+			self.learnts.push(clause_id);
+		}
+
+		clause_id
+	}
+
+	/// Number of distinct decision levels among the literals of the clause
+	pub(super) fn compute_lbd(&mut self, clause: &[Lit]) -> u32 {
+		self.lbd_stamp += 1;
+
+		let mut lbd = 0;
+		for lit in clause.iter() {
+			let level = self.decision_levels[lit.var()] as usize;
+			if self.lbd_stamps[level] != self.lbd_stamp {
+				self.lbd_stamps[level] = self.lbd_stamp;
+				lbd += 1;
+			}
+		}
+
+		lbd
+	}
+
+	/// Called for every learnt clause that takes part in conflict analysis
+	pub(super) fn bump_clause(&mut self, clause_id: ClauseId) {
+		let info = &mut self.clause_info[clause_id as usize];
+		info.used = true;
+		info.activity += self.clause_increment;
+
+		if info.activity > CLAUSE_RESCALE_LIMIT {
+			for &learnt in self.learnts.iter() {
+				self.clause_info[learnt as usize].activity /=
+					CLAUSE_RESCALE_LIMIT;
+			}
+			self.clause_increment /= CLAUSE_RESCALE_LIMIT;
+		}
+
+		// Clauses that now span fewer decision levels are promoted
+		if self.clause_info[clause_id as usize].tier != Tier::Core {
+			let clause = std::mem::take(&mut self.clauses[clause_id as usize]);
+			let lbd = self.compute_lbd(&clause);
+			self.clauses[clause_id as usize] = clause;
+
+			let info = &mut self.clause_info[clause_id as usize];
+			if lbd < info.lbd {
+				info.lbd = lbd;
+				info.tier = info.tier.min(Tier::from_lbd(lbd));
+			}
+		}
+	}
+
+	#[inline]
+	pub(super) fn decay_clause_activity(&mut self) {
+		self.clause_increment /= CLAUSE_DECAY;
+	}
+
+	/// A clause is locked while it is the antecedent of its first literal,
+	/// which is where propagation and learning put the implied literal
+	pub(super) fn is_locked(&self, clause_id: ClauseId) -> bool {
+		let lit = self.clauses[clause_id as usize][0];
+		self.antecedents[lit.var()] == clause_id
+			&& self.value(lit) == Some(true)
+	}
+
+	pub(super) fn should_reduce(&mut self) -> bool {
+		let should_reduce = self.stats.conflicts >= self.reduce_schedule.next;
+		if should_reduce {
+			self.reduce_schedule.increment += REDUCE_INCREMENT;
+			self.reduce_schedule.next =
+				self.stats.conflicts + self.reduce_schedule.increment;
+		}
+
+		should_reduce
+	}
+
+	/// Deletes the less useful half of the local learnt clauses and demotes
+	/// tier 2 clauses that have not been used since the last reduction
+	pub(super) fn reduce_db(&mut self) {
+		self.stats.reductions += 1;
+
+		let mut candidates = vec![];
+		for &clause_id in self.learnts.iter() {
+			let info = &mut self.clause_info[clause_id as usize];
+			let used = std::mem::replace(&mut info.used, false);
+
+			match info.tier {
+				Tier::Core => (),
+				Tier::Tier2 if !used => info.tier = Tier::Local,
+				Tier::Tier2 => (),
+				Tier::Local => {
+					if !used && !self.is_locked(clause_id) {
+						candidates.push(clause_id);
+					}
+				}
+			}
+		}
+
+		// Least useful first
+		candidates.sort_unstable_by(|&a, &b| {
+			let (a, b) =
+				(&self.clause_info[a as usize], &self.clause_info[b as usize]);
+			b.lbd.cmp(&a.lbd).then(a.activity.total_cmp(&b.activity))
+		});
+
+		candidates.truncate(candidates.len() / 2);
+		for &clause_id in candidates.iter() {
+			self.delete_clause(clause_id);
+		}
+		self.stats.deleted_clauses += candidates.len() as u64;
+
+		self.collect_deleted(candidates);
+	}
+
+	/// Logs the deletion of a clause and empties it. Its watches and slot
+	/// stay around until collect_deleted is called
+	pub(super) fn delete_clause(&mut self, clause_id: ClauseId) {
+		if let Some(proof) = self.proof.as_ref() {
+			proof.delete(
+				self.clause_info[clause_id as usize].proof_id,
+				&self.clauses[clause_id as usize],
+			);
+		}
+
+		self.clause_info[clause_id as usize].deleted = true;
+		self.clauses[clause_id as usize] = vec![];
+	}
+
+	/// Lists of the clauses containing each literal, indexed by literal, among
+	/// either the learnt or the original clauses
+	pub(super) fn occurrence_lists(&self, learnt: bool) -> Vec<Vec<ClauseId>> {
+		let mut occurrences = vec![vec![]; self.watches.len()];
+		for clause_id in 0..self.clauses.len() {
+			let info = &self.clause_info[clause_id];
+			if info.learnt != learnt || info.deleted {
+				continue;
+			}
+
+			for &lit in self.clauses[clause_id].iter() {
+				occurrences[lit.index()].push(clause_id as ClauseId);
+			}
+		}
+
+		occurrences
+	}
+
+	/// Drops all references to the deleted clauses so their slots can be
+	/// reused
+	pub(super) fn collect_deleted(&mut self, deleted: Vec<ClauseId>) {
+		let clause_info = &self.clause_info;
+		self.learnts
+			.retain(|&clause_id| !clause_info[clause_id as usize].deleted);
+		for watches in self.watches.iter_mut() {
+			watches.retain(|watch| !clause_info[watch.clause as usize].deleted);
+		}
+
+		// Only reuse the slots once nothing refers to them any more
+		self.free_clauses.extend(deleted);
+	}
+}
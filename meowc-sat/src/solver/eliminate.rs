@@ -0,0 +1,243 @@
+use super::{ClauseInfo, Solver};
+use crate::types::{Clause, ClauseId, Lit, VarId};
+
+// Only variables occurring in few clauses are tried, and they are only
+// eliminated when that adds no more clauses than it removes and no resolvent
+// is longer than the limit
+const OCCURRENCE_LIMIT: usize = 32;
+const RESOLVENT_LIMIT: usize = 24;
+
+impl Solver {
+	/// Bounded variable elimination as in SatELite. A variable is eliminated
+	/// by replacing the original clauses containing it with all their
+	/// non-tautological resolvents on it. The removed clauses are kept to
+	/// extend models over the variable. Assumed variables are not eliminated.
+	/// Must be called at level 0 after propagating
+	pub(super) fn eliminate_variables(&mut self) {
+		let mut occurrences = self.occurrence_lists(false);
+
+		let mut frozen = vec![false; self.num_vars as usize];
+		for &lit in self.assumptions.iter() {
+			frozen[lit.var()] = true;
+		}
+
+		// Cheapest variables first, by the number of resolvents they have
+		let mut candidates: Vec<VarId> = (1..self.num_vars as usize)
+			.filter(|&var| !frozen[var] && self.assignments[var].is_none())
+			.collect();
+		candidates.sort_by_key(|&var| {
+			let lit = Lit::from((var, true));
+			occurrences[lit.index()].len() * occurrences[(!lit).index()].len()
+		});
+
+		let mut marks = vec![false; self.watches.len()];
+		let mut deleted = vec![];
+
+		for var in candidates {
+			// Variables can be assigned by unit resolvents
+			if self.unsat {
+				break;
+			}
+			if self.assignments[var].is_some() {
+				continue;
+			}
+
+			let pos_lit = Lit::from((var, true));
+			for lit in [pos_lit, !pos_lit] {
+				let clause_info = &self.clause_info;
+				occurrences[lit.index()].retain(|&clause_id| {
+					!clause_info[clause_id as usize].deleted
+				});
+			}
+
+			let pos = occurrences[pos_lit.index()].clone();
+			let neg = occurrences[(!pos_lit).index()].clone();
+			if pos.len() + neg.len() > OCCURRENCE_LIMIT {
+				continue;
+			}
+
+			let resolvents = match self.resolvents(&pos, &neg, var, &mut marks)
+			{
+				Some(resolvents) => resolvents,
+				None => continue,
+			};
+
+			for (resolvent, antecedents) in resolvents {
+				self.add_resolvent(resolvent, antecedents, &mut occurrences);
+			}
+
+			for &clause_id in pos.iter().chain(neg.iter()) {
+				let clause = self.clauses[clause_id as usize].clone();
+				let witness =
+					*clause.iter().find(|lit| lit.var() == var).unwrap();
+				self.push_reconstruction(witness, clause);
+				self.delete_clause(clause_id);
+				deleted.push(clause_id);
+			}
+
+			// Eliminated variables are kept assigned outside of the trail so
+			// that they are never decided
+			self.eliminated[var] = true;
+			self.assignments[var] = Some(false);
+			self.stats.eliminated_vars += 1;
+		}
+
+		// Learnt clauses containing eliminated variables can be dropped as
+		// they are implied by the remaining clauses
+		let eliminated_learnts: Vec<ClauseId> = self
+			.learnts
+			.iter()
+			.copied()
+			.filter(|&clause_id| {
+				self.clauses[clause_id as usize]
+					.iter()
+					.any(|lit| self.eliminated[lit.var()])
+			})
+			.collect();
+		for clause_id in eliminated_learnts {
+			self.delete_clause(clause_id);
+			deleted.push(clause_id);
+		}
+
+		self.collect_deleted(deleted);
+
+		if !self.unsat {
+			if let Err(conflict) = self.unit_propagate() {
+				self.set_unsat(conflict);
+			}
+		}
+	}
+
+	/// All non-tautological resolvents on var of the clauses in pos and neg
+	/// along with the clauses they come from, or None if eliminating var would
+	/// add too many or too long clauses
+	fn resolvents(
+		&self,
+		pos: &[ClauseId],
+		neg: &[ClauseId],
+		var: VarId,
+		marks: &mut [bool],
+	) -> Option<Vec<(Clause, [ClauseId; 2])>> {
+		let mut resolvents = vec![];
+
+		for &pos_id in pos.iter() {
+			for &neg_id in neg.iter() {
+				let resolvent = match self.resolve([pos_id, neg_id], var, marks)
+				{
+					Some(resolvent) => resolvent,
+					None => continue,
+				};
+
+				if resolvents.len() == pos.len() + neg.len()
+					|| resolvent.len() > RESOLVENT_LIMIT
+				{
+					return None;
+				}
+				resolvents.push((resolvent, [pos_id, neg_id]));
+			}
+		}
+
+		Some(resolvents)
+	}
+
+	/// Resolvent of two clauses on var, leaving out literals that are false at
+	/// level 0. None if it is a tautology or satisfied at level 0
+	fn resolve(
+		&self,
+		clauses: [ClauseId; 2],
+		var: VarId,
+		marks: &mut [bool],
+	) -> Option<Clause> {
+		let mut resolvent = vec![];
+		let mut satisfied = false;
+
+		'clauses: for clause_id in clauses {
+			for &lit in self.clauses[clause_id as usize].iter() {
+				if lit.var() == var
+					|| marks[lit.index()]
+					|| self.value(lit) == Some(false)
+				{
+					continue;
+				}
+
+				if marks[(!lit).index()] || self.value(lit) == Some(true) {
+					satisfied = true;
+					break 'clauses;
+				}
+
+				marks[lit.index()] = true;
+				resolvent.push(lit);
+			}
+		}
+
+		for &lit in resolvent.iter() {
+			marks[lit.index()] = false;
+		}
+
+		(!satisfied).then_some(resolvent)
+	}
+
+	/// Adds a resolvent as an original clause. Units are assigned but not
+	/// propagated, as the watches of deleted clauses are still around
+	fn add_resolvent(
+		&mut self,
+		mut resolvent: Clause,
+		antecedents: [ClauseId; 2],
+		occurrences: &mut [Vec<ClauseId>],
+	) {
+		// Unit resolvents added since the resolvents of var were computed can
+		// satisfy this one or make some of its literals false
+		if resolvent.iter().any(|&lit| self.value(lit) == Some(true)) {
+			return;
+		}
+		resolvent.retain(|&lit| self.value(lit) != Some(false));
+
+		// Once the resolvent is false, the false literals left out of it make
+		// the positive antecedent unit and then the negative one conflicting
+		let hints = if self.lrat() {
+			let mut hints = vec![];
+			for clause_id in antecedents {
+				for &lit in self.clauses[clause_id as usize].iter() {
+					let unit_id = self.unit_ids[lit.var()];
+					if self.value(lit) == Some(false)
+						&& !hints.contains(&unit_id)
+					{
+						hints.push(unit_id);
+					}
+				}
+			}
+			hints.extend(
+				antecedents
+					.iter()
+					.map(|&id| self.clause_info[id as usize].proof_id),
+			);
+			hints
+		} else {
+			vec![]
+		};
+
+		let proof_id = self.new_proof_id();
+		if let Some(proof) = self.proof.as_ref() {
+			proof.add(proof_id, &resolvent, &hints);
+		}
+
+		let clause_id =
+			self.store_clause(resolvent, ClauseInfo::original(proof_id));
+		for &lit in self.clauses[clause_id as usize].iter() {
+			occurrences[lit.index()].push(clause_id);
+		}
+
+		match self.clauses[clause_id as usize].len() {
+			0 => self.set_unsat(clause_id),
+			1 => {
+				let unit = self.clauses[clause_id as usize][0];
+				match self.value(unit) {
+					Some(true) => (),
+					Some(false) => self.set_unsat(clause_id),
+					None => self.assign(unit, clause_id),
+				}
+			}
+			_ => self.watch_clause(clause_id),
+		}
+	}
+}
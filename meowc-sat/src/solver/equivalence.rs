@@ -0,0 +1,343 @@
+use super::{ClauseInfo, Solver};
+use crate::types::{Clause, ClauseId, Lit};
+
+use std::collections::{HashMap, VecDeque};
+
+type Implications = [Vec<(Lit, ClauseId)>];
+
+/// Strongly connected components of the implication graph with more than one
+/// literal, found by an iterative version of Tarjan's algorithm. Literals in
+/// one component all imply each other
+fn strongly_connected_components(implications: &Implications) -> Vec<Vec<Lit>> {
+	let len = implications.len();
+	let mut indices = vec![usize::MAX; len];
+	let mut low_links = vec![0; len];
+	let mut on_stack = vec![false; len];
+	let mut stack = vec![];
+	let mut next_index = 0;
+	let mut components = vec![];
+
+	// Literals being visited, with the next of their edges to follow
+	let mut visiting: Vec<(usize, usize)> = vec![];
+
+	for root in 2..len {
+		if indices[root] != usize::MAX || implications[root].is_empty() {
+			continue;
+		}
+
+		indices[root] = next_index;
+		low_links[root] = next_index;
+		next_index += 1;
+		stack.push(root);
+		on_stack[root] = true;
+		visiting.push((root, 0));
+
+		while let Some(&(node, edge)) = visiting.last() {
+			if let Some(&(next, _)) = implications[node].get(edge) {
+				visiting.last_mut().unwrap().1 += 1;
+
+				let next = next.index();
+				if indices[next] == usize::MAX {
+					indices[next] = next_index;
+					low_links[next] = next_index;
+					next_index += 1;
+					stack.push(next);
+					on_stack[next] = true;
+					visiting.push((next, 0));
+				} else if on_stack[next] {
+					low_links[node] = low_links[node].min(indices[next]);
+				}
+				continue;
+			}
+
+			visiting.pop();
+			if let Some(&(parent, _)) = visiting.last() {
+				low_links[parent] = low_links[parent].min(low_links[node]);
+			}
+
+			if low_links[node] == indices[node] {
+				let mut component = vec![];
+				loop {
+					let member = stack.pop().unwrap();
+					on_stack[member] = false;
+					component.push(Lit::from_index(member));
+					if member == node {
+						break;
+					}
+				}
+
+				if component.len() > 1 {
+					components.push(component);
+				}
+			}
+		}
+	}
+
+	components
+}
+
+/// Clauses along a shortest path of implications from one literal to another,
+/// in order. There must be such a path
+fn implication_path(
+	implications: &Implications,
+	from: Lit,
+	to: Lit,
+) -> Vec<ClauseId> {
+	let mut parents = HashMap::new();
+	let mut queue = VecDeque::from([from]);
+
+	while let Some(lit) = queue.pop_front() {
+		if lit == to {
+			break;
+		}
+
+		for &(next, clause_id) in implications[lit.index()].iter() {
+			if next != from && !parents.contains_key(&next.index()) {
+				parents.insert(next.index(), (lit, clause_id));
+				queue.push_back(next);
+			}
+		}
+	}
+
+	let mut path = vec![];
+	let mut lit = to;
+	while lit != from {
+		let (parent, clause_id) = parents[&lit.index()];
+		path.push(clause_id);
+		lit = parent;
+	}
+
+	path.reverse();
+	path
+}
+
+impl Solver {
+	/// Replaces every literal that is equivalent to another by a
+	/// representative of its strongly connected component in the binary
+	/// implication graph, so that its variable disappears from the clauses.
+	/// Substituted variables are reconstructed from their representatives.
+	/// Assumed variables are kept. Must be called at level 0 after
+	/// propagating
+	pub(super) fn substitute_equivalences(&mut self) {
+		let implications = self.binary_implications();
+		let components = strongly_connected_components(&implications);
+
+		let mut frozen = vec![false; self.num_vars as usize];
+		for &lit in self.assumptions.iter() {
+			frozen[lit.var()] = true;
+		}
+
+		// Representative of each substituted literal, and the proof ID of
+		// the clause (!lit v representative) used to justify substituting it
+		let mut representatives: Vec<Option<Lit>> =
+			vec![None; self.watches.len()];
+		let mut substitution_ids = vec![0; self.watches.len()];
+		let mut substituted = vec![];
+
+		for component in components {
+			// Preferring assumed variables lets them stay. A component and
+			// its negation pick the same variable, so only the component
+			// with the positive representative is handled
+			let representative = *component
+				.iter()
+				.min_by_key(|lit| (!frozen[lit.var()], lit.var()))
+				.unwrap();
+
+			if component.contains(&!representative) {
+				self.derive_failed_equivalence(&implications, representative);
+				break;
+			}
+			if !representative.as_bool() {
+				continue;
+			}
+
+			for &lit in component.iter() {
+				if lit == representative || frozen[lit.var()] {
+					continue;
+				}
+
+				representatives[lit.index()] = Some(representative);
+				representatives[(!lit).index()] = Some(!representative);
+				substituted.push((lit, representative));
+
+				if self.proof.is_some() {
+					for (lit, representative) in
+						[(lit, representative), (!lit, !representative)]
+					{
+						substitution_ids[lit.index()] = self
+							.derive_implication(
+								&implications,
+								lit,
+								representative,
+							);
+					}
+				}
+			}
+		}
+
+		if self.unsat || substituted.is_empty() {
+			return;
+		}
+
+		let mut deleted = vec![];
+		for clause_id in 0..self.clauses.len() {
+			let clause = &self.clauses[clause_id];
+			if self.clause_info[clause_id].deleted
+				|| !clause
+					.iter()
+					.any(|lit| representatives[lit.index()].is_some())
+			{
+				continue;
+			}
+
+			let mut substitution: Clause = clause
+				.iter()
+				.map(|&lit| representatives[lit.index()].unwrap_or(lit))
+				.collect();
+			substitution.sort();
+			substitution.dedup();
+
+			let clause_id = clause_id as ClauseId;
+			self.substitute_clause(
+				clause_id,
+				substitution,
+				&representatives,
+				&substitution_ids,
+			);
+			deleted.push(clause_id);
+		}
+
+		// Each substituted literal takes the value of its representative
+		for &(lit, representative) in substituted.iter() {
+			let var = lit.var();
+			let (pos, representative) = if lit.as_bool() {
+				(lit, representative)
+			} else {
+				(!lit, !representative)
+			};
+
+			self.push_reconstruction(pos, vec![pos, !representative]);
+			self.push_reconstruction(!pos, vec![!pos, representative]);
+			self.eliminated[var] = true;
+			self.assignments[var] = Some(false);
+			self.stats.substituted_vars += 1;
+		}
+
+		if let Some(proof) = self.proof.as_ref() {
+			for &(lit, representative) in substituted.iter() {
+				for (lit, representative) in
+					[(lit, representative), (!lit, !representative)]
+				{
+					proof.delete(
+						substitution_ids[lit.index()],
+						&[!lit, representative],
+					);
+				}
+			}
+		}
+
+		self.collect_deleted(deleted);
+		if !self.unsat {
+			if let Err(conflict) = self.unit_propagate() {
+				self.set_unsat(conflict);
+			}
+		}
+	}
+
+	/// Logs the clause (!from v to) given a path of implications between
+	/// them, returning its proof ID. It is not added to the clauses
+	fn derive_implication(
+		&mut self,
+		implications: &Implications,
+		from: Lit,
+		to: Lit,
+	) -> u64 {
+		let hints: Vec<u64> = if self.lrat() {
+			implication_path(implications, from, to)
+				.iter()
+				.map(|&clause_id| self.clause_info[clause_id as usize].proof_id)
+				.collect()
+		} else {
+			vec![]
+		};
+
+		let proof_id = self.new_proof_id();
+		if let Some(proof) = self.proof.as_ref() {
+			proof.add(proof_id, &[!from, to], &hints);
+		}
+		proof_id
+	}
+
+	/// Learns !lit when lit implies its own negation, which is a
+	/// contradiction when its negation also implies lit
+	fn derive_failed_equivalence(
+		&mut self,
+		implications: &Implications,
+		lit: Lit,
+	) {
+		let hints: Vec<u64> = if self.lrat() {
+			implication_path(implications, lit, !lit)
+				.iter()
+				.map(|&clause_id| self.clause_info[clause_id as usize].proof_id)
+				.collect()
+		} else {
+			vec![]
+		};
+
+		let proof_id = self.new_proof_id();
+		if let Some(proof) = self.proof.as_ref() {
+			proof.add(proof_id, &[!lit], &hints);
+		}
+
+		let clause_id =
+			self.store_clause(vec![!lit], ClauseInfo::learnt(1, proof_id));
+		self.assign(!lit, clause_id);
+		if let Err(conflict) = self.unit_propagate() {
+			self.set_unsat(conflict);
+		}
+	}
+
+	/// Replaces a clause by its substitution, unless that is a tautology. Once
+	/// the substitution is false, each substituted literal is false by its
+	/// implication from the representative, so the clause is conflicting
+	fn substitute_clause(
+		&mut self,
+		clause_id: ClauseId,
+		substitution: Clause,
+		representatives: &[Option<Lit>],
+		substitution_ids: &[u64],
+	) {
+		let tautology = substitution
+			.windows(2)
+			.any(|lits| lits[0].var() == lits[1].var());
+
+		if !tautology {
+			let clause = &self.clauses[clause_id as usize];
+			let hints = if self.lrat() {
+				let mut hints: Vec<u64> = clause
+					.iter()
+					.filter(|lit| representatives[lit.index()].is_some())
+					.map(|lit| substitution_ids[lit.index()])
+					.collect();
+				hints.push(self.clause_info[clause_id as usize].proof_id);
+				hints
+			} else {
+				vec![]
+			};
+
+			let proof_id = self.new_proof_id();
+			if let Some(proof) = self.proof.as_ref() {
+				proof.add(proof_id, &substitution, &hints);
+			}
+
+			let mut info = self.clause_info[clause_id as usize].clone();
+			info.proof_id = proof_id;
+			self.delete_clause(clause_id);
+
+			let substituted_id = self.store_clause(substitution, info);
+			self.attach_clause(substituted_id);
+		} else {
+			self.delete_clause(clause_id);
+		}
+	}
+}
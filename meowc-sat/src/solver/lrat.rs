@@ -0,0 +1,132 @@
+use super::Solver;
+use crate::types::{ClauseId, Lit};
+
+impl Solver {
+	/// Whether clauses added to the proof need hints
+	#[inline]
+	pub(super) fn lrat(&self) -> bool {
+		matches!(&self.proof, Some(proof) if proof.format().is_lrat())
+	}
+
+	#[inline]
+	pub(super) fn new_proof_id(&mut self) -> u64 {
+		let proof_id = self.next_proof_id;
+		self.next_proof_id += 1;
+		proof_id
+	}
+
+	/// LRAT hints for clause, given a clause that conflicts once the literals
+	/// of clause are false. Works back from the conflict through the
+	/// antecedents of literals not in clause, listing them so that each is
+	/// unit by the time it is reached. Literals at level 0 are justified by
+	/// their unit clauses, and binaries are the clauses (uip v -l) used to
+	/// remove literals l by binary minimization
+	pub(super) fn chain_hints(
+		&mut self,
+		clause: &[Lit],
+		binaries: &[ClauseId],
+		conflict: ClauseId,
+	) -> Vec<u64> {
+		let mut hints = vec![];
+
+		// Variables that are false when checking the hints so far
+		self.minimize_to_clear.clear();
+		for &lit in clause.iter() {
+			self.seen[lit.var()] = true;
+			self.minimize_to_clear.push(lit.var());
+		}
+
+		for &binary in binaries.iter() {
+			for &lit in self.clauses[binary as usize].iter() {
+				if !self.seen[lit.var()] {
+					self.seen[lit.var()] = true;
+					self.minimize_to_clear.push(lit.var());
+				}
+			}
+			hints.push(self.clause_info[binary as usize].proof_id);
+		}
+
+		self.minimize_stack.clear();
+		self.minimize_stack.extend(
+			self.clauses[conflict as usize].iter().map(|lit| lit.var()),
+		);
+
+		while let Some(&var) = self.minimize_stack.last() {
+			if self.seen[var] {
+				self.minimize_stack.pop();
+				continue;
+			}
+
+			if self.decision_levels[var] == 0 {
+				debug_assert!(self.unit_ids[var] != 0);
+				hints.push(self.unit_ids[var]);
+			} else {
+				// The antecedent is only unit once its other literals are
+				// false, so those are justified first
+				let antecedent = self.antecedents[var] as usize;
+				let pending = self.minimize_stack.len();
+				for &lit in self.clauses[antecedent].iter() {
+					if lit.var() != var && !self.seen[lit.var()] {
+						self.minimize_stack.push(lit.var());
+					}
+				}
+
+				if self.minimize_stack.len() > pending {
+					continue;
+				}
+				hints.push(self.clause_info[antecedent].proof_id);
+			}
+
+			self.minimize_stack.pop();
+			self.seen[var] = true;
+			self.minimize_to_clear.push(var);
+		}
+
+		hints.push(self.clause_info[conflict as usize].proof_id);
+
+		for &var in self.minimize_to_clear.iter() {
+			self.seen[var] = false;
+		}
+		self.minimize_to_clear.clear();
+
+		hints
+	}
+
+	/// Logs the unit clause of lit, which has just been assigned at level 0,
+	/// so that hints can refer to it rather than to its antecedent
+	pub(super) fn derive_unit(&mut self, lit: Lit, antecedent: ClauseId) {
+		if self.clauses[antecedent as usize].len() == 1 {
+			self.unit_ids[lit.var()] =
+				self.clause_info[antecedent as usize].proof_id;
+			return;
+		}
+
+		let hints = self.chain_hints(&[lit], &[], antecedent);
+		let proof_id = self.new_proof_id();
+		self.unit_ids[lit.var()] = proof_id;
+
+		if let Some(proof) = self.proof.as_ref() {
+			proof.add(proof_id, &[lit], &hints);
+		}
+	}
+
+	/// Logs the empty clause, given a clause that is false at level 0. This
+	/// can happen while original clauses are still being added, so no ID is
+	/// taken unless there is a proof
+	pub(super) fn derive_empty_clause(&mut self, conflict: ClauseId) {
+		if self.proof.is_none() {
+			return;
+		}
+
+		let hints = if self.lrat() {
+			self.chain_hints(&[], &[], conflict)
+		} else {
+			vec![]
+		};
+		let proof_id = self.new_proof_id();
+
+		if let Some(proof) = self.proof.as_ref() {
+			proof.add(proof_id, &[], &hints);
+		}
+	}
+}
@@ -0,0 +1,127 @@
+use super::Solver;
+use crate::types::{Clause, DecisionLevel, Lit, VarId};
+
+// Binary implication minimization has to walk the whole binary watch list of
+// the UIP, so it is only worth doing for short clauses
+const BINARY_MINIMIZATION_MAX_SIZE: usize = 30;
+
+/// Hash of a set of decision levels, used to quickly rule out literals whose
+/// implication graph reaches decision levels not in the learnt clause
+#[inline]
+fn abstract_level(level: DecisionLevel) -> u32 {
+	1 << (level & 31)
+}
+
+impl Solver {
+	/// Removes literals from a freshly derived 1UIP clause that are implied by
+	/// the other literals in the clause. Expects every literal other than the
+	/// UIP to be marked as seen, and leaves no variable marked
+	pub(super) fn minimize_clause(&mut self, clause: &mut Clause) {
+		let len = clause.len();
+		self.binary_minimized.clear();
+
+		self.minimize_to_clear.clear();
+		self.minimize_to_clear
+			.extend(clause[1..].iter().map(|lit| lit.var()));
+
+		let levels = clause[1..].iter().fold(0, |levels, lit| {
+			levels | abstract_level(self.decision_levels[lit.var()])
+		});
+
+		let mut kept = 1;
+		for i in 1..clause.len() {
+			let lit = clause[i];
+			if self.antecedents[lit.var()] == -1
+				|| !self.lit_redundant(lit, levels)
+			{
+				clause[kept] = lit;
+				kept += 1;
+			}
+		}
+		clause.truncate(kept);
+
+		for &var in self.minimize_to_clear.iter() {
+			self.seen[var] = false;
+		}
+
+		if self.config.binary_minimization
+			&& clause.len() <= BINARY_MINIMIZATION_MAX_SIZE
+		{
+			self.minimize_binary(clause);
+		}
+
+		self.stats.minimized_literals += (len - clause.len()) as u64;
+	}
+
+	/// Checks whether lit is implied by the seen literals, by walking its
+	/// antecedents depth first. Literals found to be redundant on the way are
+	/// marked as seen so they dont have to be explored again
+	fn lit_redundant(&mut self, lit: Lit, levels: u32) -> bool {
+		let top = self.minimize_to_clear.len();
+
+		self.minimize_stack.clear();
+		self.minimize_stack.push(lit.var());
+
+		while let Some(var) = self.minimize_stack.pop() {
+			let antecedent = self.antecedents[var] as usize;
+
+			for k in 0..self.clauses[antecedent].len() {
+				let other: VarId = self.clauses[antecedent][k].var();
+				if other == var
+					|| self.seen[other]
+					|| self.decision_levels[other] == 0
+				{
+					continue;
+				}
+
+				let level = self.decision_levels[other];
+				if self.antecedents[other] != -1
+					&& abstract_level(level) & levels != 0
+				{
+					self.seen[other] = true;
+					self.minimize_stack.push(other);
+					self.minimize_to_clear.push(other);
+				} else {
+					for &var in self.minimize_to_clear[top..].iter() {
+						self.seen[var] = false;
+					}
+					self.minimize_to_clear.truncate(top);
+					return false;
+				}
+			}
+		}
+
+		true
+	}
+
+	/// Removes literals l for which a binary clause (uip v -l) exists, as
+	/// resolving with it just removes l from the clause. The binary clauses
+	/// used are kept in binary_minimized
+	fn minimize_binary(&mut self, clause: &mut Clause) {
+		for lit in clause[1..].iter() {
+			self.seen[lit.var()] = true;
+		}
+
+		for watch in self.watches[(!clause[0]).index()].iter() {
+			let other = watch.blocker;
+			if self.seen[other.var()]
+				&& self.value(other) == Some(true)
+				&& self.clauses[watch.clause as usize].len() == 2
+			{
+				self.seen[other.var()] = false;
+				self.binary_minimized.push(watch.clause);
+			}
+		}
+
+		let mut kept = 1;
+		for i in 1..clause.len() {
+			let var = clause[i].var();
+			if self.seen[var] {
+				self.seen[var] = false;
+				clause[kept] = clause[i];
+				kept += 1;
+			}
+		}
+		clause.truncate(kept);
+	}
+}
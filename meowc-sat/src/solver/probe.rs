@@ -0,0 +1,121 @@
+use super::{ClauseInfo, Solver};
+use crate::types::{ClauseId, Lit};
+
+// Propagations each round of probing may use
+const PROBE_EFFORT: u64 = 100000;
+
+const PROBE_FIRST: u64 = 5000;
+const PROBE_INCREMENT: u64 = 5000;
+
+#[derive(Clone)]
+pub(super) struct ProbeSchedule {
+	next: u64,
+	increment: u64,
+}
+
+impl Default for ProbeSchedule {
+	fn default() -> Self {
+		Self {
+			next: PROBE_FIRST,
+			increment: PROBE_FIRST,
+		}
+	}
+}
+
+impl Solver {
+	pub(super) fn should_probe(&mut self) -> bool {
+		let schedule = &mut self.probe_schedule;
+		let should_probe = self.stats.conflicts >= schedule.next;
+		if should_probe {
+			schedule.increment += PROBE_INCREMENT;
+			schedule.next = self.stats.conflicts + schedule.increment;
+		}
+
+		should_probe
+	}
+
+	/// Binary implication graph of the binary clauses whose literals are both
+	/// unassigned, indexed by literal. An edge from a to b comes with the
+	/// clause (!a v b) it stands for
+	pub(super) fn binary_implications(&self) -> Vec<Vec<(Lit, ClauseId)>> {
+		let mut implications = vec![vec![]; self.watches.len()];
+		for clause_id in 0..self.clauses.len() {
+			let clause = &self.clauses[clause_id];
+			if self.clause_info[clause_id].deleted
+				|| clause.len() != 2
+				|| clause.iter().any(|&lit| self.value(lit).is_some())
+			{
+				continue;
+			}
+
+			let (a, b) = (clause[0], clause[1]);
+			implications[(!a).index()].push((b, clause_id as ClauseId));
+			implications[(!b).index()].push((a, clause_id as ClauseId));
+		}
+
+		implications
+	}
+
+	/// Probes the roots of the binary implication graph, which imply other
+	/// literals but are implied by none. A root that leads to a conflict by
+	/// propagation is a failed literal, so its negation is learnt as a unit.
+	/// Probing only roots covers every literal they imply, as those would
+	/// fail too. Must be called at level 0 after propagating
+	pub(super) fn probe(&mut self) {
+		let implications = self.binary_implications();
+		let roots: Vec<Lit> = (2..implications.len())
+			.filter(|&index| {
+				!implications[index].is_empty()
+					&& implications[index ^ 1].is_empty()
+			})
+			.map(Lit::from_index)
+			.collect();
+
+		// Backtracking saves the probed values as phases, which shouldnt
+		// affect the search
+		let phases = self.phases.clone();
+		let limit = self.stats.propagations + PROBE_EFFORT;
+
+		for root in roots {
+			if self.unsat || self.stats.propagations > limit {
+				break;
+			}
+			if self.value(root).is_some() {
+				continue;
+			}
+
+			self.new_decision_level();
+			self.assign(root, -1);
+
+			let conflict = match self.unit_propagate() {
+				Ok(()) => {
+					self.backtrack(0);
+					continue;
+				}
+				Err(conflict) => conflict,
+			};
+
+			self.stats.failed_literals += 1;
+			let hints = if self.lrat() {
+				self.chain_hints(&[!root], &[], conflict)
+			} else {
+				vec![]
+			};
+			self.backtrack(0);
+
+			let proof_id = self.new_proof_id();
+			if let Some(proof) = self.proof.as_ref() {
+				proof.add(proof_id, &[!root], &hints);
+			}
+
+			let clause_id =
+				self.store_clause(vec![!root], ClauseInfo::learnt(1, proof_id));
+			self.assign(!root, clause_id);
+			if let Err(conflict) = self.unit_propagate() {
+				self.set_unsat(conflict);
+			}
+		}
+
+		self.phases = phases;
+	}
+}
@@ -0,0 +1,92 @@
+use super::{ClauseInfo, Solver};
+use crate::types::{Clause, ClauseId, Lit, VarId};
+
+impl Solver {
+	/// Keeps a clause removed by simplification so that models can be extended
+	/// to satisfy it. The witness is the literal of the clause that is made
+	/// true when the rest of the model doesn't satisfy it
+	pub(super) fn push_reconstruction(&mut self, witness: Lit, clause: Clause) {
+		self.witnesses[witness.var()] = true;
+		self.reconstruction.push((witness, clause));
+	}
+
+	/// Whether var is eliminated or a witness, so that it can't be used until
+	/// the removed clauses are restored. Variables without occurrences are
+	/// eliminated without removing any clauses, so aren't witnesses
+	#[inline]
+	pub(super) fn is_removed(&self, var: VarId) -> bool {
+		self.eliminated[var] || self.witnesses[var]
+	}
+
+	/// Extends the assignment over eliminated variables, flipping witnesses
+	/// where needed, so that it satisfies the removed clauses. Later removals
+	/// are undone first, as their clauses can contain variables eliminated
+	/// after earlier removals
+	pub(super) fn extend_model(&mut self) {
+		let assignments = &mut self.assignments;
+		for (witness, clause) in self.reconstruction.iter().rev() {
+			let satisfied = clause
+				.iter()
+				.any(|&lit| assignments[lit.var()] == Some(lit.as_bool()));
+			if !satisfied {
+				assignments[witness.var()] = Some(witness.as_bool());
+			}
+		}
+	}
+
+	/// Adds back every clause removed by simplification, so that eliminated
+	/// and witness variables can be used in new clauses and assumptions.
+	///
+	/// The clauses are logged as additions with their witness first, in the
+	/// reverse of the order they were removed in, which makes each one RAT on
+	/// its witness in a DRAT proof. Learnt clauses on witness variables could
+	/// break that, so are deleted first unless they are locked. Nothing is
+	/// removed this way when writing LRAT, which has no RAT steps
+	pub(super) fn restore_eliminated(&mut self) {
+		debug_assert!(!self.lrat());
+		self.backtrack(0);
+
+		let deleted: Vec<ClauseId> = self
+			.learnts
+			.iter()
+			.copied()
+			.filter(|&clause_id| {
+				!self.is_locked(clause_id)
+					&& self.clauses[clause_id as usize]
+						.iter()
+						.any(|lit| self.witnesses[lit.var()])
+			})
+			.collect();
+		for &clause_id in deleted.iter() {
+			self.delete_clause(clause_id);
+		}
+		self.collect_deleted(deleted);
+
+		for var in 1..self.num_vars as usize {
+			self.witnesses[var] = false;
+			if self.eliminated[var] {
+				self.eliminated[var] = false;
+				self.unassign(var);
+			}
+		}
+
+		let removed = std::mem::take(&mut self.reconstruction);
+		for (witness, clause) in removed.into_iter().rev() {
+			self.restore_clause(witness, clause);
+		}
+	}
+
+	fn restore_clause(&mut self, witness: Lit, mut clause: Clause) {
+		let pos = clause.iter().position(|&lit| lit == witness).unwrap();
+		clause.swap(0, pos);
+
+		let proof_id = self.new_proof_id();
+		if let Some(proof) = self.proof.as_ref() {
+			proof.add(proof_id, &clause, &[]);
+		}
+
+		let clause_id =
+			self.store_clause(clause, ClauseInfo::original(proof_id));
+		self.attach_clause(clause_id);
+	}
+}
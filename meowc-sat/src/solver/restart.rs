@@ -0,0 +1,163 @@
+use super::Solver;
+use crate::{
+	config::{RestartPolicy, SearchMode},
+	ema::Ema,
+	luby::Luby,
+};
+
+const LUBY_SCALE: u64 = 512;
+
+const GEOMETRIC_FIRST: f64 = 100.0;
+const GEOMETRIC_FACTOR: f64 = 1.5;
+
+// Glucose restarts happen once the fast LBD average exceeds the slow one by
+// the margin. They are blocked once enough conflicts have happened to know
+// the usual trail length, whenever the trail is much longer than that
+const LBD_FAST_ALPHA: f64 = 0.03;
+const LBD_SLOW_ALPHA: f64 = 1e-5;
+const GLUCOSE_MARGIN: f64 = 1.1;
+const GLUCOSE_MIN_INTERVAL: u64 = 2;
+
+const TRAIL_ALPHA: f64 = 2e-4;
+const BLOCK_MARGIN: f64 = 1.4;
+const BLOCK_MIN_CONFLICTS: u64 = 10000;
+const BLOCK_INTERVAL: u64 = 50;
+
+// Each phase in one search mode is longer than the last by this factor
+const MODE_PHASE_GROWTH: u64 = 2;
+
+#[derive(Clone)]
+pub(super) struct RestartSchedule {
+	next: u64,
+	luby: Luby,
+	geometric_interval: f64,
+
+	lbd_fast: Ema,
+	lbd_slow: Ema,
+	trail: Ema,
+
+	next_mode_switch: Option<u64>,
+	mode_phase: u64,
+}
+
+impl Default for RestartSchedule {
+	fn default() -> Self {
+		Self {
+			next: 0,
+			luby: Default::default(),
+			geometric_interval: GEOMETRIC_FIRST,
+
+			lbd_fast: Ema::new(LBD_FAST_ALPHA),
+			lbd_slow: Ema::new(LBD_SLOW_ALPHA),
+			trail: Ema::new(TRAIL_ALPHA),
+
+			next_mode_switch: None,
+			mode_phase: 0,
+		}
+	}
+}
+
+impl Solver {
+	fn restart_policy(&self) -> RestartPolicy {
+		match self.mode {
+			SearchMode::Focused => self.config.focused_restarts,
+			SearchMode::Stable => self.config.stable_restarts,
+		}
+	}
+
+	/// Goes back to the configured search mode and starts the restart and
+	/// mode switching schedules again
+	pub(super) fn reset_search_mode(&mut self) {
+		self.mode = self.config.mode;
+
+		let schedule = &mut self.restart_schedule;
+		schedule.mode_phase = self.config.mode_switch_interval.unwrap_or(0);
+		schedule.next_mode_switch = self
+			.config
+			.mode_switch_interval
+			.map(|interval| self.stats.conflicts + interval);
+
+		self.schedule_restart();
+	}
+
+	fn schedule_restart(&mut self) {
+		let policy = self.restart_policy();
+		let schedule = &mut self.restart_schedule;
+
+		let interval = match policy {
+			RestartPolicy::Glucose => GLUCOSE_MIN_INTERVAL,
+			RestartPolicy::Luby => schedule.luby.next() * LUBY_SCALE,
+			RestartPolicy::Geometric => {
+				let interval = schedule.geometric_interval;
+				schedule.geometric_interval *= GEOMETRIC_FACTOR;
+				interval as u64
+			}
+		};
+		schedule.next = self.stats.conflicts + interval;
+	}
+
+	/// Called with the LBD of each learnt clause before backtracking, while
+	/// the trail still holds the conflicting assignment
+	pub(super) fn update_restart_averages(&mut self, lbd: u32) {
+		let policy = self.restart_policy();
+		let schedule = &mut self.restart_schedule;
+
+		schedule.lbd_fast.update(lbd as f64);
+		schedule.lbd_slow.update(lbd as f64);
+
+		// An unusually long trail suggests the search is close to a model
+		let trail = self.trail.len() as f64;
+		if policy == RestartPolicy::Glucose
+			&& self.stats.conflicts > BLOCK_MIN_CONFLICTS
+			&& trail > BLOCK_MARGIN * schedule.trail.value()
+		{
+			schedule.next =
+				schedule.next.max(self.stats.conflicts + BLOCK_INTERVAL);
+			self.stats.blocked_restarts += 1;
+		}
+		schedule.trail.update(trail);
+	}
+
+	pub(super) fn should_restart(&mut self) -> bool {
+		let schedule = &self.restart_schedule;
+		if self.stats.conflicts < schedule.next {
+			return false;
+		}
+
+		if self.restart_policy() == RestartPolicy::Glucose
+			&& schedule.lbd_fast.value()
+				<= GLUCOSE_MARGIN * schedule.lbd_slow.value()
+		{
+			return false;
+		}
+
+		self.schedule_restart();
+		true
+	}
+
+	#[inline]
+	pub(super) fn should_switch_mode(&self) -> bool {
+		matches!(
+			self.restart_schedule.next_mode_switch,
+			Some(next) if self.stats.conflicts >= next
+		)
+	}
+
+	/// Switches to the other search mode for a longer phase than the last,
+	/// restarting the search
+	pub(super) fn switch_mode(&mut self) {
+		self.stats.mode_switches += 1;
+		self.mode = match self.mode {
+			SearchMode::Focused => SearchMode::Stable,
+			SearchMode::Stable => SearchMode::Focused,
+		};
+
+		let schedule = &mut self.restart_schedule;
+		schedule.mode_phase *= MODE_PHASE_GROWTH;
+		schedule.next_mode_switch =
+			Some(self.stats.conflicts + schedule.mode_phase);
+
+		self.backtrack(0);
+		self.schedule_restart();
+	}
+}
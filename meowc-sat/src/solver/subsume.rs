@@ -0,0 +1,236 @@
+use super::Solver;
+use crate::types::{Clause, ClauseId, Lit};
+
+// Clauses longer than this are not used to subsume others, and literals with
+// more occurrences than this are not searched
+const SUBSUMER_LIMIT: usize = 32;
+const OCCURRENCE_LIMIT: usize = 1000;
+
+const SUBSUME_FIRST: u64 = 10000;
+const SUBSUME_INCREMENT: u64 = 5000;
+
+#[derive(Clone)]
+pub(super) struct SubsumeSchedule {
+	next: u64,
+	increment: u64,
+}
+
+impl Default for SubsumeSchedule {
+	fn default() -> Self {
+		Self {
+			next: SUBSUME_FIRST,
+			increment: SUBSUME_FIRST,
+		}
+	}
+}
+
+/// Bit set of the variables of a clause modulo 64. A clause can only subsume
+/// or strengthen clauses whose signatures include its own
+fn signature(clause: &[Lit]) -> u64 {
+	clause
+		.iter()
+		.fold(0, |signature, lit| signature | 1 << (lit.var() % 64))
+}
+
+impl Solver {
+	pub(super) fn should_subsume(&mut self) -> bool {
+		let schedule = &mut self.subsume_schedule;
+		let should_subsume = self.stats.conflicts >= schedule.next;
+		if should_subsume {
+			schedule.increment += SUBSUME_INCREMENT;
+			schedule.next = self.stats.conflicts + schedule.increment;
+		}
+
+		should_subsume
+	}
+
+	/// Removes clauses subsumed by other clauses, and strengthens clauses
+	/// by self-subsuming resolution, removing !l from D when C contains l and
+	/// the rest of C is contained in D. Works on either the learnt or the
+	/// original clauses, which are never mixed. Must be called at level 0
+	/// after propagating
+	pub(super) fn subsume(&mut self, learnt: bool) {
+		let mut occurrences = self.occurrence_lists(learnt);
+		let mut signatures = vec![0; self.clauses.len()];
+
+		// Shorter clauses subsume more, so go first. Strengthened clauses are
+		// added to the end as they may subsume others now
+		let mut candidates = vec![];
+		for (clause_id, clause) in self.clauses.iter().enumerate() {
+			let info = &self.clause_info[clause_id];
+			if info.learnt == learnt && !info.deleted {
+				signatures[clause_id] = signature(clause);
+				candidates.push(clause_id as ClauseId);
+			}
+		}
+		candidates
+			.sort_by_key(|&clause_id| self.clauses[clause_id as usize].len());
+
+		let mut marks = vec![false; self.watches.len()];
+		let mut deleted = vec![];
+
+		let mut i = 0;
+		while i < candidates.len() && !self.unsat {
+			let clause_id = candidates[i];
+			i += 1;
+
+			let clause = &self.clauses[clause_id as usize];
+			if self.clause_info[clause_id as usize].deleted
+				|| clause.len() > SUBSUMER_LIMIT
+			{
+				continue;
+			}
+
+			// Every clause C subsumes or strengthens contains one of the
+			// literals of its variables, so only the rarest has to be searched
+			let occurrence_count = |lit: Lit| {
+				occurrences[lit.index()].len()
+					+ occurrences[(!lit).index()].len()
+			};
+			let lit = match clause
+				.iter()
+				.min_by_key(|&&lit| occurrence_count(lit))
+			{
+				Some(&lit) if occurrence_count(lit) <= OCCURRENCE_LIMIT => lit,
+				_ => continue,
+			};
+
+			let others: Vec<ClauseId> = occurrences[lit.index()]
+				.iter()
+				.chain(occurrences[(!lit).index()].iter())
+				.copied()
+				.collect();
+
+			for other_id in others {
+				let other = &self.clauses[other_id as usize];
+				if other_id == clause_id
+					|| self.clause_info[other_id as usize].deleted
+					|| other.len() < self.clauses[clause_id as usize].len()
+					|| signatures[clause_id as usize]
+						& !signatures[other_id as usize]
+						!= 0
+				{
+					continue;
+				}
+
+				match self.subsumes(clause_id, other_id, &mut marks) {
+					Some(None) => {
+						self.merge_subsumed(clause_id, other_id);
+						self.delete_clause(other_id);
+						deleted.push(other_id);
+						self.stats.subsumed_clauses += 1;
+					}
+					Some(Some(flipped)) => {
+						let strengthened =
+							self.strengthen(clause_id, other_id, !flipped);
+						deleted.push(other_id);
+						self.stats.strengthened_clauses += 1;
+
+						signatures.resize(self.clauses.len(), 0);
+						signatures[strengthened as usize] =
+							signature(&self.clauses[strengthened as usize]);
+						for &lit in self.clauses[strengthened as usize].iter() {
+							occurrences[lit.index()].push(strengthened);
+						}
+						candidates.push(strengthened);
+					}
+					None => (),
+				}
+			}
+		}
+
+		self.collect_deleted(deleted);
+
+		if !self.unsat {
+			if let Err(conflict) = self.unit_propagate() {
+				self.set_unsat(conflict);
+			}
+		}
+	}
+
+	/// Whether clause subsumes other. Some(Some(l)) if it only does once l
+	/// is flipped, in which case !l can be removed from other
+	fn subsumes(
+		&self,
+		clause_id: ClauseId,
+		other_id: ClauseId,
+		marks: &mut [bool],
+	) -> Option<Option<Lit>> {
+		let other = &self.clauses[other_id as usize];
+		for &lit in other.iter() {
+			marks[lit.index()] = true;
+		}
+
+		let mut flipped = None;
+		let mut subsumes = true;
+		for &lit in self.clauses[clause_id as usize].iter() {
+			if marks[lit.index()] {
+				continue;
+			}
+
+			if flipped.is_none() && marks[(!lit).index()] {
+				flipped = Some(lit);
+			} else {
+				subsumes = false;
+				break;
+			}
+		}
+
+		for &lit in other.iter() {
+			marks[lit.index()] = false;
+		}
+
+		subsumes.then_some(flipped)
+	}
+
+	/// Keeps the better tier and LBD of two learnt clauses when one subsumes
+	/// the other
+	fn merge_subsumed(&mut self, clause_id: ClauseId, subsumed_id: ClauseId) {
+		let subsumed = self.clause_info[subsumed_id as usize].clone();
+		let info = &mut self.clause_info[clause_id as usize];
+		if info.learnt {
+			info.lbd = info.lbd.min(subsumed.lbd);
+			info.tier = info.tier.min(subsumed.tier);
+		}
+	}
+
+	/// Replaces other with a copy without lit, which is the resolvent of it
+	/// with clause. Returns the ID of the new clause
+	fn strengthen(
+		&mut self,
+		clause_id: ClauseId,
+		other_id: ClauseId,
+		lit: Lit,
+	) -> ClauseId {
+		let strengthened: Clause = self.clauses[other_id as usize]
+			.iter()
+			.copied()
+			.filter(|&other| other != lit)
+			.collect();
+
+		// Once the strengthened clause is false, clause is unit on !lit, which
+		// makes other conflicting
+		let hints = if self.lrat() {
+			vec![
+				self.clause_info[clause_id as usize].proof_id,
+				self.clause_info[other_id as usize].proof_id,
+			]
+		} else {
+			vec![]
+		};
+
+		let proof_id = self.new_proof_id();
+		if let Some(proof) = self.proof.as_ref() {
+			proof.add(proof_id, &strengthened, &hints);
+		}
+		self.delete_clause(other_id);
+
+		let mut info = self.clause_info[other_id as usize].clone();
+		info.deleted = false;
+		info.proof_id = proof_id;
+
+		let strengthened_id = self.store_clause(strengthened, info);
+		self.attach_clause(strengthened_id);
+		strengthened_id
+	}
+}
@@ -0,0 +1,459 @@
+use super::Solver;
+use crate::{
+	config::{Budget, Config},
+	random::Random,
+	stats::Stats,
+	types::{Clause, Lit, SatResult},
+};
+
+fn lits(clause: &[i32]) -> Clause {
+	clause.iter().map(|&lit| Lit::from(lit)).collect()
+}
+
+fn solver(num_vars: u32, clauses: &[Vec<i32>], config: Config) -> Solver {
+	let mut solver = Solver::with_config(num_vars, config);
+	for clause in clauses {
+		solver.add_clause(&lits(clause));
+	}
+	solver
+}
+
+/// Random clauses of two or three literals over the first num_vars variables
+fn random_clauses(
+	random: &mut Random,
+	num_vars: u32,
+	num_clauses: usize,
+) -> Vec<Vec<i32>> {
+	(0..num_clauses)
+		.map(|_| {
+			let len = 2 + random.next() % 2;
+			(0..len)
+				.map(|_| {
+					let var = (random.next() % num_vars as u64) as i32 + 1;
+					if random.next_bool() {
+						var
+					} else {
+						-var
+					}
+				})
+				.collect()
+		})
+		.collect()
+}
+
+/// Whether some assignment of the first num_vars variables satisfies the
+/// clauses, trying every one of them
+fn brute_force(num_vars: u32, clauses: &[Vec<i32>]) -> bool {
+	(0..1u32 << num_vars).any(|bits| {
+		clauses.iter().all(|clause| {
+			clause.iter().any(|&lit| {
+				let value = bits >> (lit.unsigned_abs() - 1) & 1 == 1;
+				value == (lit > 0)
+			})
+		})
+	})
+}
+
+/// Solves and checks the result against brute force, and that the model
+/// assigns every variable and satisfies the clauses
+fn check(
+	solver: &mut Solver,
+	num_vars: u32,
+	clauses: &[Vec<i32>],
+) -> SatResult {
+	check_assuming(solver, num_vars, clauses, &[])
+}
+
+/// Solves under the assumptions and checks the result as check does. When
+/// unsatisfiable, the final conflict has to be made of assumptions that are
+/// enough to make the clauses unsatisfiable
+fn check_assuming(
+	solver: &mut Solver,
+	num_vars: u32,
+	clauses: &[Vec<i32>],
+	assumptions: &[i32],
+) -> SatResult {
+	let result = solver.solve_with_assumptions(&lits(assumptions));
+	let assumed: Vec<Vec<i32>> =
+		assumptions.iter().map(|&lit| vec![lit]).collect();
+	let satisfiable = brute_force(num_vars, &[clauses, &assumed].concat());
+	assert_eq!(
+		result == SatResult::Sat,
+		satisfiable,
+		"{:?} assuming {:?}",
+		clauses,
+		assumptions
+	);
+
+	if satisfiable {
+		let model = solver.model().unwrap();
+		assert_eq!(model.len(), num_vars as usize);
+		for clause in clauses.iter().chain(assumed.iter()) {
+			assert!(
+				lits(clause).iter().any(|lit| model.contains(lit)),
+				"{:?} violates {:?} of {:?}",
+				model,
+				clause,
+				clauses
+			);
+		}
+	} else {
+		assert!(solver.model().is_none());
+
+		let failed = solver.final_conflict();
+		assert!(failed.iter().all(|&lit| solver.failed(lit)));
+		let failed: Vec<Vec<i32>> = failed
+			.iter()
+			.map(|&lit| {
+				assert!(assumptions.contains(&i32::from(lit)));
+				vec![i32::from(lit)]
+			})
+			.collect();
+		assert!(!brute_force(num_vars, &[clauses, &failed].concat()));
+	}
+
+	result
+}
+
+/// Config with every preprocessing pass off, for testing passes alone
+fn no_preprocessing() -> Config {
+	Config {
+		subsumption: false,
+		elimination: false,
+		blocked_elimination: false,
+		covered_elimination: false,
+		probing: false,
+		..Default::default()
+	}
+}
+
+/// Solves and checks random formulas, returning the total of the stat count
+/// picks out over all of them
+fn random_stat(
+	seed: u64,
+	config: Config,
+	count: impl Fn(&Stats) -> u64,
+) -> u64 {
+	let mut random = Random::new(seed);
+	let mut total = 0;
+	for _ in 0..300 {
+		let num_vars = 1 + (random.next() % 10) as u32;
+		let num_clauses = 1 + (random.next() % (5 * num_vars as u64)) as usize;
+		let clauses = random_clauses(&mut random, num_vars, num_clauses);
+
+		let mut solver = solver(num_vars, &clauses, config.clone());
+		check(&mut solver, num_vars, &clauses);
+		total += count(&solver.stats);
+	}
+	total
+}
+
+#[test]
+fn conflicting_unit_resolvents() {
+	// Eliminating 4 gives the resolvents 2 and -2
+	let clauses = vec![
+		vec![-3, 4],
+		vec![1, 2, 3],
+		vec![-2, 7],
+		vec![-1],
+		vec![-4, -2],
+		vec![2, -4],
+		vec![4, -7],
+	];
+	let config = Config {
+		subsumption: false,
+		probing: false,
+		..Default::default()
+	};
+
+	let mut solver = solver(7, &clauses, config);
+	assert_eq!(check(&mut solver, 7, &clauses), SatResult::Unsat);
+}
+
+#[test]
+fn random_elimination() {
+	let mut random = Random::new(1);
+	for _ in 0..500 {
+		let num_vars = 1 + (random.next() % 10) as u32;
+		let num_clauses = 1 + (random.next() % (5 * num_vars as u64)) as usize;
+		let clauses = random_clauses(&mut random, num_vars, num_clauses);
+		let config = Config {
+			subsumption: false,
+			probing: false,
+			..Default::default()
+		};
+
+		let mut solver = solver(num_vars, &clauses, config);
+		check(&mut solver, num_vars, &clauses);
+	}
+}
+
+#[test]
+fn elimination_alone() {
+	let config = Config {
+		elimination: true,
+		..no_preprocessing()
+	};
+
+	// Eliminating 1 leaves 2 3 and -2 -3
+	let clauses = vec![vec![1, 2], vec![-1, 3], vec![-2, -3]];
+	let mut sat = solver(3, &clauses, config.clone());
+	assert_eq!(check(&mut sat, 3, &clauses), SatResult::Sat);
+	assert!(sat.stats.eliminated_vars > 0);
+
+	let clauses = vec![vec![1, 2], vec![-1, 2], vec![1, -2], vec![-1, -2]];
+	let mut unsat = solver(2, &clauses, config.clone());
+	assert_eq!(check(&mut unsat, 2, &clauses), SatResult::Unsat);
+
+	assert!(random_stat(3, config, |stats| stats.eliminated_vars) > 0);
+}
+
+#[test]
+fn subsumption_alone() {
+	let config = Config {
+		subsumption: true,
+		..no_preprocessing()
+	};
+
+	// 1 2 subsumes 1 2 3, and -1 -3 4 strengthens -1 3 4 to -1 4
+	let clauses = vec![
+		vec![1, 2],
+		vec![1, 2, 3],
+		vec![-1, -3, 4],
+		vec![-1, 3, 4],
+		vec![-2, -4],
+	];
+	let mut solver = solver(4, &clauses, config.clone());
+	assert_eq!(check(&mut solver, 4, &clauses), SatResult::Sat);
+	assert!(solver.stats.subsumed_clauses > 0);
+	assert!(solver.stats.strengthened_clauses > 0);
+
+	assert!(random_stat(4, config.clone(), |stats| stats.subsumed_clauses) > 0);
+	assert!(random_stat(5, config, |stats| stats.strengthened_clauses) > 0);
+}
+
+#[test]
+fn probing_alone() {
+	let config = Config {
+		probing: true,
+		..no_preprocessing()
+	};
+
+	// 1 implies 2 and -2 so fails, and 3 and 4 are equivalent
+	let clauses = vec![
+		vec![-1, 2],
+		vec![-1, -2],
+		vec![-3, 4],
+		vec![3, -4],
+		vec![3, 5],
+		vec![-4, -5, 1],
+	];
+	let mut solver = solver(5, &clauses, config.clone());
+	assert_eq!(check(&mut solver, 5, &clauses), SatResult::Sat);
+	assert!(solver.stats.failed_literals > 0);
+	assert!(solver.stats.substituted_vars > 0);
+
+	assert!(random_stat(6, config.clone(), |stats| stats.failed_literals) > 0);
+	assert!(random_stat(7, config, |stats| stats.substituted_vars) > 0);
+}
+
+#[test]
+fn blocked_elimination_alone() {
+	let config = Config {
+		blocked_elimination: true,
+		..no_preprocessing()
+	};
+
+	// Resolving 1 2 with -1 -2 on 1 gives 2 -2
+	let clauses = vec![vec![1, 2], vec![-1, -2], vec![2, 3], vec![-3, -2]];
+	let mut solver = solver(3, &clauses, config.clone());
+	assert_eq!(check(&mut solver, 3, &clauses), SatResult::Sat);
+	assert!(solver.stats.blocked_clauses > 0);
+
+	assert!(random_stat(8, config.clone(), |stats| stats.blocked_clauses) > 0);
+
+	let config = Config {
+		covered_elimination: true,
+		..config
+	};
+	assert!(random_stat(9, config, |stats| stats.covered_clauses) > 0);
+}
+
+#[test]
+fn variable_without_occurrences() {
+	// Variables 1 and 3 have no occurrences and are eliminated
+	let mut clauses = vec![vec![-4], vec![2, -4]];
+	let mut solver = solver(4, &clauses, Default::default());
+	check(&mut solver, 4, &clauses);
+
+	check_assuming(&mut solver, 4, &clauses, &[-1, 3]);
+	clauses.push(vec![3]);
+	solver.add_clause(&lits(&[3]));
+	check(&mut solver, 4, &clauses);
+}
+
+#[test]
+fn random_incremental() {
+	let mut random = Random::new(2);
+	for _ in 0..500 {
+		let num_vars = 1 + (random.next() % 10) as u32;
+		let num_clauses = 1 + (random.next() % (3 * num_vars as u64)) as usize;
+		let mut clauses = random_clauses(&mut random, num_vars, num_clauses);
+		let config = Config {
+			probing: false,
+			..Default::default()
+		};
+		let mut solver = solver(num_vars, &clauses, config);
+
+		for _ in 0..5 {
+			let assumptions = &random_clauses(&mut random, num_vars, 1)[0];
+			check_assuming(&mut solver, num_vars, &clauses, assumptions);
+
+			if check(&mut solver, num_vars, &clauses) == SatResult::Unsat {
+				break;
+			}
+			for clause in random_clauses(&mut random, num_vars, 2) {
+				solver.add_clause(&lits(&clause));
+				clauses.push(clause);
+			}
+		}
+	}
+}
+
+#[test]
+fn assumptions() {
+	let clauses = vec![vec![-1, 2], vec![-2, 3], vec![4, 5]];
+	let mut solver = solver(5, &clauses, Default::default());
+
+	check_assuming(&mut solver, 5, &clauses, &[1]);
+	assert_eq!(solver.value(Lit::from(3)), Some(true));
+
+	let result = check_assuming(&mut solver, 5, &clauses, &[4, 1, -3]);
+	assert_eq!(result, SatResult::Unsat);
+	assert!(solver.failed(Lit::from(1)) && solver.failed(Lit::from(-3)));
+	assert!(!solver.failed(Lit::from(4)));
+
+	// Assumptions only hold for the call they are passed to
+	assert_eq!(check(&mut solver, 5, &clauses), SatResult::Sat);
+}
+
+#[test]
+fn contradictory_assumptions() {
+	let clauses = vec![vec![1, 2]];
+	let mut solver = solver(2, &clauses, Default::default());
+
+	let result = check_assuming(&mut solver, 2, &clauses, &[2, 1, -1]);
+	assert_eq!(result, SatResult::Unsat);
+	assert!(solver.failed(Lit::from(1)) && solver.failed(Lit::from(-1)));
+	assert!(!solver.failed(Lit::from(2)));
+}
+
+#[test]
+fn add_clause_after_solve() {
+	let mut clauses = vec![vec![1, 2], vec![-1, 2]];
+	let mut solver = solver(2, &clauses, Default::default());
+	assert_eq!(check(&mut solver, 2, &clauses), SatResult::Sat);
+
+	clauses.push(vec![1, -2]);
+	solver.add_clause(&lits(&[1, -2]));
+	assert!(solver.model().is_none());
+	assert_eq!(check(&mut solver, 2, &clauses), SatResult::Sat);
+	assert_eq!(
+		check_assuming(&mut solver, 2, &clauses, &[-1]),
+		SatResult::Unsat
+	);
+	assert_eq!(solver.final_conflict(), &[Lit::from(-1)]);
+
+	clauses.push(vec![-1, -2]);
+	solver.add_clause(&lits(&[-1, -2]));
+	assert_eq!(
+		check_assuming(&mut solver, 2, &clauses, &[1]),
+		SatResult::Unsat
+	);
+	// The formula is unsatisfiable without the assumptions
+	assert!(solver.final_conflict().is_empty());
+}
+
+/// Clauses saying that each of pigeons pigeons is in one of holes holes, and
+/// no two share a hole. Variable i * holes + j + 1 puts pigeon i in hole j
+fn pigeonhole(pigeons: i32, holes: i32) -> Vec<Vec<i32>> {
+	let var = |pigeon: i32, hole: i32| pigeon * holes + hole + 1;
+	let mut clauses: Vec<Vec<i32>> = (0..pigeons)
+		.map(|pigeon| (0..holes).map(|hole| var(pigeon, hole)).collect())
+		.collect();
+	for hole in 0..holes {
+		for a in 0..pigeons {
+			for b in a + 1..pigeons {
+				clauses.push(vec![-var(a, hole), -var(b, hole)]);
+			}
+		}
+	}
+	clauses
+}
+
+#[test]
+fn budget() {
+	let clauses = pigeonhole(4, 3);
+	let mut solver = solver(12, &clauses, no_preprocessing());
+
+	solver.set_budget(Budget {
+		conflicts: Some(1),
+		..Default::default()
+	});
+	assert_eq!(solver.solve(), SatResult::Unknown);
+	assert!(solver.model().is_none());
+	assert!(solver.final_conflict().is_empty());
+
+	// The budget applies to each call on its own
+	solver.set_budget(Budget {
+		decisions: Some(1),
+		..Default::default()
+	});
+	assert_eq!(solver.solve(), SatResult::Unknown);
+
+	solver.set_budget(Default::default());
+	assert_eq!(check(&mut solver, 12, &clauses), SatResult::Unsat);
+}
+
+#[test]
+fn terminator() {
+	let clauses = vec![vec![1, 2], vec![-1, 2]];
+	let mut solver = solver(2, &clauses, Default::default());
+
+	let terminator = solver.terminator();
+	std::thread::spawn(move || terminator.terminate())
+		.join()
+		.unwrap();
+	assert_eq!(solver.solve(), SatResult::Unknown);
+	assert!(solver.model().is_none());
+	// It stays set until reset
+	assert_eq!(solver.solve(), SatResult::Unknown);
+
+	solver.terminator().reset();
+	assert_eq!(check(&mut solver, 2, &clauses), SatResult::Sat);
+}
+
+#[test]
+fn model() {
+	let clauses = vec![vec![1], vec![-2], vec![2, 3]];
+	for config in [no_preprocessing(), Default::default()] {
+		let mut solver = solver(3, &clauses, config);
+		assert_eq!(check(&mut solver, 3, &clauses), SatResult::Sat);
+
+		let model = solver.model().unwrap();
+		assert_eq!(model, lits(&[1, -2, 3]));
+		assert!(solver.assignment().eq(model.iter().copied()));
+		for &lit in model.iter() {
+			assert_eq!(solver.value(lit), Some(true));
+			assert_eq!(solver.value(!lit), Some(false));
+		}
+
+		let mut line = Vec::new();
+		solver.write_assignment(&mut line).unwrap();
+		assert_eq!(line, b"v 1 -2 3 0\n");
+
+		// The model is discarded once the formula changes
+		solver.add_clause(&lits(&[-1, 2, 3]));
+		assert!(solver.model().is_none());
+	}
+}
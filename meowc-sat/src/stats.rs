@@ -1,14 +1,42 @@
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Stats {
+	pub decisions: u64,
 	pub propagations: u64,
 	pub conflicts: u64,
 	pub restarts: u64,
+	pub blocked_restarts: u64,
+	pub mode_switches: u64,
+	pub reductions: u64,
+	pub deleted_clauses: u64,
+	pub learnt_literals: u64,
+	pub minimized_literals: u64,
+	pub subsumed_clauses: u64,
+	pub strengthened_clauses: u64,
+	pub eliminated_vars: u64,
+	pub blocked_clauses: u64,
+	pub covered_clauses: u64,
+	pub failed_literals: u64,
+	pub substituted_vars: u64,
 }
 
 impl Stats {
 	pub fn print_summary(&self) {
-		println!("c propagations: {:9}", self.propagations);
-		println!("c    conflicts: {:9}", self.conflicts);
-		println!("c     restarts: {:9}", self.restarts);
+		println!("c        decisions: {:9}", self.decisions);
+		println!("c     propagations: {:9}", self.propagations);
+		println!("c        conflicts: {:9}", self.conflicts);
+		println!("c         restarts: {:9}", self.restarts);
+		println!("c blocked restarts: {:9}", self.blocked_restarts);
+		println!("c         switches: {:9}", self.mode_switches);
+		println!("c       reductions: {:9}", self.reductions);
+		println!("c          deleted: {:9}", self.deleted_clauses);
+		println!("c      learnt lits: {:9}", self.learnt_literals);
+		println!("c        minimized: {:9}", self.minimized_literals);
+		println!("c         subsumed: {:9}", self.subsumed_clauses);
+		println!("c     strengthened: {:9}", self.strengthened_clauses);
+		println!("c       eliminated: {:9}", self.eliminated_vars);
+		println!("c  blocked clauses: {:9}", self.blocked_clauses);
+		println!("c  covered clauses: {:9}", self.covered_clauses);
+		println!("c           failed: {:9}", self.failed_literals);
+		println!("c      substituted: {:9}", self.substituted_vars);
 	}
 }
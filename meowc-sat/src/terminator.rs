@@ -0,0 +1,27 @@
+use std::sync::{
+	atomic::{AtomicBool, Ordering},
+	Arc,
+};
+
+/// Handle that stops a running solve from another thread or a signal handler,
+/// making it return Unknown. It stays set until reset, so any later calls to
+/// solve also stop straight away
+#[derive(Debug, Clone, Default)]
+pub struct Terminator(Arc<AtomicBool>);
+
+impl Terminator {
+	#[inline]
+	pub fn terminate(&self) {
+		self.0.store(true, Ordering::Relaxed);
+	}
+
+	#[inline]
+	pub fn reset(&self) {
+		self.0.store(false, Ordering::Relaxed);
+	}
+
+	#[inline]
+	pub fn is_terminated(&self) -> bool {
+		self.0.load(Ordering::Relaxed)
+	}
+}
@@ -9,6 +9,8 @@ pub type DecisionLevel = u32;
 pub enum SatResult {
 	Sat,
 	Unsat,
+	/// The solver ran out of budget before finding a result
+	Unknown,
 }
 
 impl fmt::Display for SatResult {
@@ -19,6 +21,7 @@ impl fmt::Display for SatResult {
 			match self {
 				SatResult::Sat => "SATISFIABLE",
 				SatResult::Unsat => "UNSATISFIABLE",
+				SatResult::Unknown => "UNKNOWN",
 			}
 		)
 	}
@@ -39,6 +42,19 @@ impl Lit {
 	pub fn as_bool(&self) -> bool {
 		(*self).into()
 	}
+
+	/// Dense index of the literal, suitable for indexing per-literal tables
+	/// of length 2 * (max variable + 1)
+	#[inline]
+	pub fn index(&self) -> usize {
+		self.0.get() as usize
+	}
+
+	/// Literal with the given index, which must be at least 2
+	#[inline]
+	pub fn from_index(index: usize) -> Self {
+		Self::from((index >> 1, index & 1 == 1))
+	}
 }
 
 impl Not for Lit {
@@ -0,0 +1,147 @@
+use crate::types::VarId;
+
+/// Variable move to front decision queue, as used in CaDiCaL. Variables are
+/// kept in a doubly linked list ordered by when they were last bumped, with
+/// the most recently bumped at the end. Decisions are taken from the end of
+/// the queue, and a search pointer caches the position before which all
+/// remaining unassigned variables lie. Variable 0 is used as the null link
+#[derive(Clone)]
+pub struct Vmtf {
+	prev: Vec<VarId>,
+	next: Vec<VarId>,
+	stamps: Vec<u64>,
+
+	first: VarId,
+	last: VarId,
+	search: VarId,
+	stamp: u64,
+}
+
+impl Vmtf {
+	/// Expects num_vars to include the unused variable 0, which is never
+	/// enqueued
+	pub fn new(num_vars: usize) -> Self {
+		let last = num_vars.saturating_sub(1);
+
+		Self {
+			prev: (0..num_vars).map(|var| var.saturating_sub(1)).collect(),
+			next: (0..num_vars)
+				.map(|var| if var == last { 0 } else { var + 1 })
+				.collect(),
+			stamps: (0..num_vars as u64).collect(),
+
+			first: if last == 0 { 0 } else { 1 },
+			last,
+			search: last,
+			stamp: last as u64,
+		}
+	}
+
+	#[inline]
+	pub fn stamp(&self, var: VarId) -> u64 {
+		self.stamps[var]
+	}
+
+	/// Moves var to the end of the queue. Only assigned variables should be
+	/// bumped, unassigned ones have to be reported with on_unassign afterwards
+	pub fn bump(&mut self, var: VarId) {
+		self.stamp += 1;
+		self.stamps[var] = self.stamp;
+
+		if var == self.last {
+			return;
+		}
+
+		// Unlink
+		let (prev, next) = (self.prev[var], self.next[var]);
+		if prev == 0 {
+			self.first = next;
+		} else {
+			self.next[prev] = next;
+		}
+		self.prev[next] = prev;
+
+		// Append
+		self.prev[var] = self.last;
+		self.next[var] = 0;
+		self.next[self.last] = var;
+		self.last = var;
+	}
+
+	/// Keeps the search pointer in front of every unassigned variable
+	#[inline]
+	pub fn on_unassign(&mut self, var: VarId) {
+		if self.stamps[var] > self.stamps[self.search] {
+			self.search = var;
+		}
+	}
+
+	/// Returns the most recently bumped unassigned variable
+	pub fn next_unassigned(
+		&mut self,
+		assignments: &[Option<bool>],
+	) -> Option<VarId> {
+		let mut var = self.search;
+		while var != 0 && assignments[var].is_some() {
+			var = self.prev[var];
+		}
+
+		if var == 0 {
+			return None;
+		}
+
+		self.search = var;
+		Some(var)
+	}
+}
+#[cfg(test)]
+mod tests {
+	use super::Vmtf;
+
+	#[test]
+	fn most_recently_bumped_first() {
+		let mut vmtf = Vmtf::new(4);
+		let mut assignments = vec![None; 4];
+
+		// Variables start queued in order, so the last is picked first
+		for var in [3, 2, 1] {
+			assert_eq!(vmtf.next_unassigned(&assignments), Some(var));
+			assignments[var] = Some(true);
+		}
+		assert_eq!(vmtf.next_unassigned(&assignments), None);
+
+		vmtf.bump(2);
+		vmtf.bump(1);
+		assert!(vmtf.stamp(1) > vmtf.stamp(2));
+		assert!(vmtf.stamp(2) > vmtf.stamp(3));
+
+		// The queue is now 3 2 1
+		for var in [3, 2] {
+			assignments[var] = None;
+			vmtf.on_unassign(var);
+		}
+		assert_eq!(vmtf.next_unassigned(&assignments), Some(2));
+		assignments[2] = Some(false);
+		assert_eq!(vmtf.next_unassigned(&assignments), Some(3));
+
+		assignments[1] = None;
+		vmtf.on_unassign(1);
+		assert_eq!(vmtf.next_unassigned(&assignments), Some(1));
+	}
+
+	#[test]
+	fn bump_first_and_last() {
+		let mut vmtf = Vmtf::new(4);
+		vmtf.bump(1);
+		vmtf.bump(1);
+		assert_eq!((vmtf.first, vmtf.last), (2, 1));
+		assert_eq!((vmtf.prev[1], vmtf.next[1]), (3, 0));
+		assert_eq!(vmtf.prev[2], 0);
+	}
+
+	#[test]
+	fn no_variables() {
+		let mut vmtf = Vmtf::new(1);
+		assert_eq!(vmtf.next_unassigned(&[None]), None);
+	}
+}
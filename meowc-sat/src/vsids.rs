@@ -0,0 +1,223 @@
+use crate::types::VarId;
+
+const RESCALE_LIMIT: f64 = 1e100;
+
+/// Variable State Independent Decaying Sum decision heuristic. Variables are
+/// bumped when they take part in conflict analysis, and the bump increment is
+/// grown geometrically after each conflict so older bumps decay relative to
+/// newer ones. Unassigned variables are kept in a binary max heap ordered by
+/// activity
+#[derive(Clone)]
+pub struct Vsids {
+	activity: Vec<f64>,
+	increment: f64,
+
+	heap: Vec<VarId>,
+	positions: Vec<Option<usize>>,
+}
+
+impl Vsids {
+	/// Expects num_vars to include the unused variable 0, which is never
+	/// inserted into the heap
+	pub fn new(num_vars: usize) -> Self {
+		Self {
+			activity: vec![0.0; num_vars],
+			increment: 1.0,
+
+			heap: (1..num_vars).collect(),
+			positions: (0..num_vars).map(|var| var.checked_sub(1)).collect(),
+		}
+	}
+
+	#[inline]
+	pub fn contains(&self, var: VarId) -> bool {
+		self.positions[var].is_some()
+	}
+
+	pub fn insert(&mut self, var: VarId) {
+		if self.contains(var) {
+			return;
+		}
+
+		self.positions[var] = Some(self.heap.len());
+		self.heap.push(var);
+		self.sift_up(self.heap.len() - 1);
+	}
+
+	pub fn pop_max(&mut self) -> Option<VarId> {
+		let max = *self.heap.first()?;
+
+		let last = unsafe { self.heap.pop().unwrap_unchecked() };
+		self.positions[max] = None;
+
+		if !self.heap.is_empty() {
+			self.heap[0] = last;
+			self.positions[last] = Some(0);
+			self.sift_down(0);
+		}
+
+		Some(max)
+	}
+
+	pub fn bump(&mut self, var: VarId) {
+		self.activity[var] += self.increment;
+
+		if self.activity[var] > RESCALE_LIMIT {
+			for activity in self.activity.iter_mut() {
+				*activity /= RESCALE_LIMIT;
+			}
+			self.increment /= RESCALE_LIMIT;
+		}
+
+		if let Some(pos) = self.positions[var] {
+			self.sift_up(pos);
+		}
+	}
+
+	/// Decays all activities by decay, which should be in (0, 1]. This is done
+	/// by increasing the increment used for future bumps instead
+	#[inline]
+	pub fn decay(&mut self, decay: f64) {
+		self.increment /= decay;
+	}
+
+	fn sift_up(&mut self, mut pos: usize) {
+		let var = self.heap[pos];
+
+		while pos > 0 {
+			let parent = (pos - 1) / 2;
+			if self.activity[self.heap[parent]] >= self.activity[var] {
+				break;
+			}
+
+			self.heap[pos] = self.heap[parent];
+			self.positions[self.heap[pos]] = Some(pos);
+			pos = parent;
+		}
+
+		self.heap[pos] = var;
+		self.positions[var] = Some(pos);
+	}
+
+	fn sift_down(&mut self, mut pos: usize) {
+		let var = self.heap[pos];
+
+		loop {
+			let left = 2 * pos + 1;
+			if left >= self.heap.len() {
+				break;
+			}
+
+			let right = left + 1;
+			let child = if right < self.heap.len()
+				&& self.activity[self.heap[right]]
+					> self.activity[self.heap[left]]
+			{
+				right
+			} else {
+				left
+			};
+
+			if self.activity[self.heap[child]] <= self.activity[var] {
+				break;
+			}
+
+			self.heap[pos] = self.heap[child];
+			self.positions[self.heap[pos]] = Some(pos);
+			pos = child;
+		}
+
+		self.heap[pos] = var;
+		self.positions[var] = Some(pos);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Vsids;
+	use crate::random::Random;
+
+	fn pop_all(vsids: &mut Vsids) -> Vec<usize> {
+		std::iter::from_fn(|| vsids.pop_max()).collect()
+	}
+
+	#[test]
+	fn pops_by_activity() {
+		let mut vsids = Vsids::new(6);
+		vsids.bump(3);
+		vsids.bump(3);
+		vsids.bump(5);
+		// Bumps after decaying count for more
+		vsids.decay(0.25);
+		vsids.bump(2);
+
+		let order = pop_all(&mut vsids);
+		assert_eq!(order[..3], [2, 3, 5]);
+		assert_eq!(order.len(), 5);
+		assert!(order.contains(&1) && order.contains(&4));
+	}
+
+	#[test]
+	fn insert() {
+		let mut vsids = Vsids::new(4);
+		vsids.bump(1);
+		vsids.bump(1);
+		vsids.bump(3);
+		pop_all(&mut vsids);
+		assert!(!vsids.contains(1));
+
+		// Bumps while out of the heap still count once reinserted
+		vsids.bump(2);
+		vsids.bump(2);
+		vsids.bump(2);
+		vsids.insert(3);
+		vsids.insert(2);
+		vsids.insert(1);
+		vsids.insert(2);
+		assert!(vsids.contains(2));
+		assert_eq!(pop_all(&mut vsids), [2, 1, 3]);
+	}
+
+	#[test]
+	fn rescale() {
+		let mut vsids = Vsids::new(4);
+		vsids.bump(1);
+		vsids.decay(1e-60);
+		vsids.bump(2);
+		vsids.decay(1e-60);
+		// Takes the activity of 3 past the limit, so every activity is scaled
+		// down
+		vsids.bump(3);
+
+		assert!(vsids.activity.iter().all(|&activity| activity <= 1e100));
+		assert!(vsids.increment <= 1e100);
+		assert_eq!(pop_all(&mut vsids), [3, 2, 1]);
+	}
+
+	#[test]
+	fn random_bumps() {
+		let mut random = Random::new(1);
+		for _ in 0..100 {
+			let num_vars = 2 + (random.next() % 50) as usize;
+			let mut vsids = Vsids::new(num_vars);
+			for _ in 0..200 {
+				let var = 1 + (random.next() % (num_vars as u64 - 1)) as usize;
+				match random.next() % 4 {
+					0 => vsids.insert(var),
+					1 => vsids.decay(0.9),
+					_ => vsids.bump(var),
+				}
+				if random.next() % 8 == 0 {
+					vsids.pop_max();
+				}
+			}
+
+			let order = pop_all(&mut vsids);
+			assert!(order
+				.windows(2)
+				.all(|vars| vsids.activity[vars[0]] >= vsids.activity[vars[1]]));
+			assert!(vsids.heap.is_empty());
+			assert!(vsids.positions.iter().all(Option::is_none));
+		}
+	}
+}